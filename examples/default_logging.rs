@@ -14,17 +14,17 @@ struct CliArgs {
 fn main() {
     let args = CliArgs::parse();
 
-    // Initialize logging with the flags from clap
-    clap_logflag::init_logging!(
+    // Initialize logging with the flags from clap. Logs are flushed when `_logging` is dropped at the end of `main`.
+    let _logging = clap_logflag::init_logging!(
         args.log
             // If no `--log` arguments are present, log to stderr with the default level filter
             // Note that if the user passes in `--log none`, this will not trigger the default
             // and logging will be disabled instead. The default is only used if no `--log`
-            // arguments are present.
-            .or_default(LoggingConfig::new(vec![LogDestinationConfig {
-                destination: clap_logflag::LogDestination::Stderr,
-                level: None,
-            }],)),
+            // arguments are present, or if they only turn destinations off, e.g. `--log OFF:stderr`.
+            .or_default(LoggingConfig::new(vec![LogDestinationConfig::new(
+                clap_logflag::LogDestination::Stderr,
+                None,
+            )])),
         LOG_DEFAULT_LEVEL
     );
 
@@ -1,7 +1,7 @@
 //! This example is not a real world example, it is used in our integration tests as a test binary.
 
 use clap::Parser;
-use clap_logflag::{LogDestinationConfig, LoggingConfig};
+use clap_logflag::{InitOptions, LogDestinationConfig, LoggingConfig, PanicHook};
 use log::LevelFilter;
 
 #[derive(Debug, Parser)]
@@ -14,20 +14,27 @@ struct CliArgs {
     /// We're just doing that here to make this example useful for our integration tests.
     #[arg(long)]
     default_level: LevelFilter,
+
+    /// Panic after issuing the log messages. Used to test the panic hook.
+    #[arg(long)]
+    panic: bool,
 }
 
 fn main() {
     let args = CliArgs::parse();
 
-    // Initialize logging with the flags from clap
-    clap_logflag::init_logging!(
+    // Initialize logging with the flags from clap. Logs are flushed when `_logging` is dropped at the end of `main`.
+    let _logging = clap_logflag::init_logging!(
         args.log
             // If no `--log` arguments are present, log to stderr but only log warnings and errors.
-            .or_default(LoggingConfig::new(vec![LogDestinationConfig {
-                destination: clap_logflag::LogDestination::Stderr,
-                level: Some(LevelFilter::Warn),
-            },],)),
-        args.default_level
+            .or_default(LoggingConfig::new(vec![LogDestinationConfig::new(
+                clap_logflag::LogDestination::Stderr,
+                Some(LevelFilter::Warn),
+            )])),
+        args.default_level,
+        InitOptions {
+            panic_hook: PanicHook::Enabled,
+        },
     );
 
     log::trace!("Some trace log");
@@ -35,4 +42,8 @@ fn main() {
     log::info!("Some info log");
     log::warn!("Some warn log");
     log::error!("Some error log");
+
+    if args.panic {
+        panic!("Some panic");
+    }
 }
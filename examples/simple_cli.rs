@@ -14,8 +14,8 @@ struct CliArgs {
 fn main() {
     let args = CliArgs::parse();
 
-    // Initialize logging with the flags from clap
-    clap_logflag::init_logging!(
+    // Initialize logging with the flags from clap. Logs are flushed when `_logging` is dropped at the end of `main`.
+    let _logging = clap_logflag::init_logging!(
         args.log
             // If no `--log` arguments are present, disable logging.
             // You can change this to define the default behavior,
@@ -0,0 +1,40 @@
+use clap::Parser;
+use clap_logflag::LoggingConfig;
+use log::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt as _;
+use tracing_subscriber::util::SubscriberInitExt as _;
+
+#[derive(Debug, Parser)]
+struct CliArgs {
+    // ... your other cli args ...
+    //
+    // Use this to add the log flags to your application
+    #[clap(flatten)]
+    log: clap_logflag::LogArgs,
+}
+
+fn main() {
+    let args = CliArgs::parse();
+
+    // Initialize tracing with the flags from clap
+    let layer = clap_logflag::tracing_layer!(
+        args.log
+            // If no `--log` arguments are present, disable logging.
+            .or_default(LoggingConfig::disabled()),
+        // Any `--log` argument that doesn't define a level filter will use the
+        // default level filter defined here, `Info` in this example.
+        LevelFilter::Info,
+    );
+    // Logs are flushed when `_logging` is dropped at the end of `main`
+    let _logging = layer.guard();
+    tracing_subscriber::registry().with(layer).init();
+
+    // Issue some log messages. Fields of the spans are added to the log messages.
+    let span = tracing::info_span!("request", id = 5);
+    let _span = span.enter();
+    tracing::trace!("Some trace log");
+    tracing::debug!("Some debug log");
+    tracing::info!("Some info log");
+    tracing::warn!(reason = "example", "Some warn log");
+    tracing::error!("Some error log");
+}
@@ -0,0 +1,188 @@
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
+use std::time::SystemTime;
+
+use log::kv::{Key, Value, VisitSource};
+
+use super::sink::LogSink;
+use super::utils::collect_key_values;
+
+/// An owned copy of a log record, passed to the callback of a [CallbackSink].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    /// The level of the log record
+    pub level: log::Level,
+
+    /// The target of the log record
+    pub target: String,
+
+    /// The log message, without any formatting added
+    pub message: String,
+
+    /// The structured key-values of the log record, with the values converted to strings
+    pub key_values: Vec<(String, String)>,
+
+    /// The time at which the record was logged
+    pub timestamp: SystemTime,
+}
+
+impl LogRecord {
+    fn from_record(record: &log::Record) -> Self {
+        let KeyValueCollector(key_values) = collect_key_values(record);
+        Self {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+            key_values,
+            timestamp: SystemTime::now(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct KeyValueCollector(Vec<(String, String)>);
+
+impl<'kvs> VisitSource<'kvs> for KeyValueCollector {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        self.0.push((key.to_string(), value.to_string()));
+        Ok(())
+    }
+}
+
+/// A [LogSink] that forwards each log record as a [LogRecord] to a closure or a channel.
+///
+/// This is useful for GUI or TUI applications that want to show log messages in the application,
+/// while still allowing users to configure other log destinations with `--log`.
+///
+/// # Example
+/// ```rust
+/// use clap_logflag::{CallbackSink, LogDestination, LogDestinationConfig, LoggingConfig};
+/// use log::LevelFilter;
+///
+/// let (sender, receiver) = std::sync::mpsc::channel();
+/// clap_logflag::register_sink("panel", CallbackSink::channel(sender));
+///
+/// // In a real application, this would come from `args.log.or_default(...)`
+/// let mut config = LoggingConfig::disabled();
+/// // Always log to the panel, in addition to the destinations given with `--log`
+/// config.add_destination(LogDestinationConfig::new(
+///     LogDestination::custom("panel", "").unwrap(),
+///     Some(LevelFilter::Info),
+/// ));
+/// let _logging = clap_logflag::init_logging!(config, LevelFilter::Info);
+///
+/// log::info!("Some info log");
+/// assert_eq!("Some info log", receiver.recv().unwrap().message);
+/// ```
+pub struct CallbackSink {
+    callback: Arc<dyn Fn(LogRecord) + Send + Sync>,
+}
+
+impl CallbackSink {
+    /// Create a sink that calls the given closure for each log record.
+    ///
+    /// The closure is called on the thread that logged the record, so it should return quickly.
+    pub fn new(callback: impl Fn(LogRecord) + Send + Sync + 'static) -> Self {
+        Self {
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// Create a sink that sends each log record to the given channel.
+    ///
+    /// If the receiver was dropped, log records are discarded.
+    pub fn channel(sender: Sender<LogRecord>) -> Self {
+        Self::new(move |record| {
+            let _ = sender.send(record);
+        })
+    }
+}
+
+impl LogSink for CallbackSink {
+    fn build(&self, _argument: &str) -> anyhow::Result<Box<dyn log::Log>> {
+        Ok(Box::new(CallbackLogger {
+            callback: Arc::clone(&self.callback),
+        }))
+    }
+}
+
+struct CallbackLogger {
+    callback: Arc<dyn Fn(LogRecord) + Send + Sync>,
+}
+
+impl log::Log for CallbackLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        (self.callback)(LogRecord::from_record(record));
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    fn log(logger: &dyn log::Log) {
+        let key_values = [("user", "alice"), ("attempt", "3")];
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("test log message"))
+                .level(Level::Warn)
+                .target("my-test")
+                .key_values(&key_values)
+                .build(),
+        );
+    }
+
+    fn assert_record(record: &LogRecord, logged_after: SystemTime) {
+        assert_eq!(Level::Warn, record.level);
+        assert_eq!("my-test", record.target);
+        assert_eq!("test log message", record.message);
+        assert_eq!(
+            vec![
+                ("user".to_string(), "alice".to_string()),
+                ("attempt".to_string(), "3".to_string()),
+            ],
+            record.key_values
+        );
+        assert!(record.timestamp >= logged_after);
+    }
+
+    #[test]
+    fn channel() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let logger = CallbackSink::channel(sender).build("").unwrap();
+        let before = SystemTime::now();
+        log(logger.as_ref());
+        assert_record(&receiver.try_recv().unwrap(), before);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn channel_with_dropped_receiver() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let logger = CallbackSink::channel(sender).build("").unwrap();
+        drop(receiver);
+        // Doesn't panic
+        log(logger.as_ref());
+    }
+
+    #[test]
+    fn closure() {
+        let records = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let records_clone = Arc::clone(&records);
+        let logger = CallbackSink::new(move |record| records_clone.lock().unwrap().push(record))
+            .build("")
+            .unwrap();
+        let before = SystemTime::now();
+        log(logger.as_ref());
+        let records = records.lock().unwrap();
+        assert_eq!(1, records.len());
+        assert_record(&records[0], before);
+    }
+}
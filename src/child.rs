@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use super::config::{LogDestination, LogDestinationConfig, LoggingConfig};
+use super::parser::ParseError;
+use super::utils::lock;
+
+/// The configuration logging was last initialized with, see [LoggingConfig::active].
+static ACTIVE_CONFIG: Mutex<Option<LoggingConfig>> = Mutex::new(None);
+
+/// Remember the configuration logging is initialized with, with the default level filled in,
+/// so that child processes get the same levels even if their default level differs.
+///
+/// Destinations that don't log anything are left out, see [LoggingConfig::to_args].
+pub fn set_active(config: &LoggingConfig, default_level: log::LevelFilter) {
+    let destinations = config
+        .destinations()
+        .iter()
+        .filter_map(|destination| {
+            let level = destination.level.unwrap_or(default_level);
+            // A range like `..INFO` is empty if the default level is more severe, e.g. ERROR
+            let empty = destination
+                .most_severe
+                .is_some_and(|most_severe| level < most_severe.to_level_filter());
+            if empty || level == log::LevelFilter::Off {
+                return None;
+            }
+            Some(LogDestinationConfig {
+                level: Some(level),
+                ..destination.clone()
+            })
+        })
+        .collect();
+    *lock(&ACTIVE_CONFIG) = Some(LoggingConfig::new(destinations));
+}
+
+impl LoggingConfig {
+    /// The configuration that logging was initialized with by [init_logging!](crate::init_logging) or `tracing_layer!`,
+    /// or `None` if logging wasn't initialized yet.
+    ///
+    /// Destinations without a level filter get the default level that logging was initialized with.
+    pub fn active() -> Option<LoggingConfig> {
+        lock(&ACTIVE_CONFIG).clone()
+    }
+
+    /// Read a configuration from an environment variable, e.g. one set with [LoggingConfig::set_command_env].
+    /// Returns `None` if the environment variable isn't set.
+    ///
+    /// The value has the same syntax as the `--log` argument, with multiple log definitions separated by spaces.
+    pub fn from_env(name: &str) -> Result<Option<LoggingConfig>, ParseError> {
+        match std::env::var(name) {
+            Ok(value) => value.parse().map(Some),
+            Err(std::env::VarError::NotPresent) => Ok(None),
+            Err(std::env::VarError::NotUnicode(_)) => Err(ParseError::new(format!(
+                "The environment variable `{name}` isn't valid UTF-8"
+            ))),
+        }
+    }
+
+    /// The `--log` arguments that configure a child process the same way, e.g. `["--log", "INFO:stderr"]`.
+    ///
+    /// Destinations with level `OFF` are left out, because if a child only got `--log OFF:destination` arguments,
+    /// it would keep its other default destinations, see [LogArgs::or_default](crate::LogArgs::or_default).
+    /// If logging is disabled, or all destinations are off, this returns `["--log", "none"]`,
+    /// so the child doesn't fall back to its default configuration.
+    pub fn to_args(&self) -> Vec<String> {
+        let args: Vec<String> = self
+            .destinations()
+            .iter()
+            .filter(|destination| destination.level != Some(log::LevelFilter::Off))
+            .flat_map(|destination| ["--log".to_string(), destination.to_string()])
+            .collect();
+        if args.is_empty() {
+            return vec!["--log".to_string(), "none".to_string()];
+        }
+        args
+    }
+
+    /// Add the `--log` arguments from [LoggingConfig::to_args] to a command that spawns a child process.
+    pub fn add_command_args(&self, command: &mut Command) {
+        command.args(self.to_args());
+    }
+
+    /// Set an environment variable for a command that spawns a child process, which the child can read with [LoggingConfig::from_env].
+    pub fn set_command_env(&self, command: &mut Command, name: &str) {
+        command.env(name, self.to_string());
+    }
+
+    /// Rewrite the paths of file destinations, including the sinks of ring buffers, e.g. so that each child process logs to its own file.
+    ///
+    /// Placeholders like `{pid}` are expanded by the child process, so they refer to the child:
+    /// ```rust
+    /// # use clap_logflag::LoggingConfig;
+    /// # let config: LoggingConfig = "INFO:file:/var/log/app.log".parse().unwrap();
+    /// let child_config = config.map_file_paths(|path| path.with_extension("{pid}.log"));
+    /// assert_eq!("INFO:file:/var/log/app.{pid}.log", child_config.to_string());
+    /// ```
+    pub fn map_file_paths(&self, mut map: impl FnMut(&Path) -> PathBuf) -> LoggingConfig {
+        let destinations = self
+            .destinations()
+            .iter()
+            .map(|destination| LogDestinationConfig {
+                level: destination.level,
+                destination: map_file_path(&destination.destination, &mut map),
+                most_severe: destination.most_severe,
+                filters: destination.filters.clone(),
+            })
+            .collect();
+        LoggingConfig::new(destinations)
+    }
+}
+
+fn map_file_path(
+    destination: &LogDestination,
+    map: &mut impl FnMut(&Path) -> PathBuf,
+) -> LogDestination {
+    match destination {
+        LogDestination::File { path, options } => LogDestination::File {
+            path: map(path),
+            options: options.clone(),
+        },
+        LogDestination::RingBuffer { capacity, sink } => LogDestination::RingBuffer {
+            capacity: *capacity,
+            sink: Box::new(map_file_path(sink, map)),
+        },
+        destination => destination.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn config(input: &str) -> LoggingConfig {
+        input.parse().unwrap()
+    }
+
+    #[rstest]
+    #[case("none", &["--log", "none"])]
+    #[case("stderr", &["--log", "stderr"])]
+    #[case(
+        "INFO:stderr DEBUG:pipe:\"gzip > /a.gz\"",
+        &["--log", "INFO:stderr", "--log", "DEBUG:pipe:\"gzip > /a.gz\""]
+    )]
+    #[case("OFF:stderr", &["--log", "none"])]
+    #[case("OFF:stderr INFO:syslog", &["--log", "INFO:syslog"])]
+    fn to_args(#[case] input: &str, #[case] expected: &[&str]) {
+        assert_eq!(expected, config(input).to_args());
+    }
+
+    /// The config a child process that uses clap-logflag gets from the `--log` arguments of the parent's config
+    fn child_config(parent_config: &LoggingConfig, child_default: LoggingConfig) -> LoggingConfig {
+        use clap::Parser as _;
+
+        #[derive(clap::Parser)]
+        struct Args {
+            #[clap(flatten)]
+            log: crate::LogArgs,
+        }
+
+        let args =
+            Args::parse_from(std::iter::once("child".to_string()).chain(parent_config.to_args()));
+        args.log.or_default(child_default)
+    }
+
+    #[test]
+    fn args_round_trip_through_clap() {
+        let config = config("INFO:stderr DEBUG:file:\"/path with spaces/a;b.log\";truncate none");
+        assert_eq!(config, child_config(&config, LoggingConfig::disabled()));
+    }
+
+    #[test]
+    fn add_command_args() {
+        let mut command = Command::new("child");
+        config("WARN:syslog").add_command_args(&mut command);
+        assert_eq!(
+            vec!["--log", "WARN:syslog"],
+            command.get_args().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn set_command_env() {
+        let mut command = Command::new("child");
+        config("WARN:syslog file:/a.log").set_command_env(&mut command, "CHILD_LOG");
+        assert_eq!(
+            vec![(
+                std::ffi::OsStr::new("CHILD_LOG"),
+                Some(std::ffi::OsStr::new("WARN:syslog file:/a.log"))
+            )],
+            command.get_envs().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn from_env() {
+        assert_eq!(
+            None,
+            LoggingConfig::from_env("CLAP_LOGFLAG_TEST_UNSET_LOG").unwrap()
+        );
+    }
+
+    #[test]
+    fn map_file_paths() {
+        let config = config(
+            "INFO:file:/var/log/app.log;truncate stderr TRACE:ringbuffer:10:file:/var/log/trace.log",
+        );
+        let mapped = config.map_file_paths(|path| path.with_extension("{pid}.log"));
+        assert_eq!(
+            "INFO:file:/var/log/app.{pid}.log;truncate stderr TRACE:ringbuffer:10:file:/var/log/trace.{pid}.log",
+            mapped.to_string()
+        );
+    }
+
+    #[test]
+    fn set_active_leaves_out_destinations_that_are_off() {
+        set_active(
+            &config("stderr DEBUG:syslog ..ERROR:file:/a.log ..INFO:file:/b.log"),
+            log::LevelFilter::Warn,
+        );
+        assert_eq!(
+            Some(config("WARN:stderr DEBUG:syslog WARN..ERROR:file:/a.log")),
+            LoggingConfig::active()
+        );
+
+        // The active config is global, so this is part of the same test.
+        // The parent doesn't log anything, so the child must not fall back to its default destinations.
+        set_active(
+            &config("..INFO:file:/a.log OFF:stderr"),
+            log::LevelFilter::Error,
+        );
+        let active = LoggingConfig::active().unwrap();
+        assert_eq!(LoggingConfig::disabled(), active);
+        assert_eq!(vec!["--log", "none"], active.to_args());
+        assert_eq!(
+            LoggingConfig::disabled(),
+            child_config(&active, config("syslog"))
+        );
+    }
+}
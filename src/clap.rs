@@ -1,6 +1,6 @@
 use clap::Parser;
 
-use crate::{LogDestinationConfig, LoggingConfig};
+use crate::{LogDestination, LogDestinationConfig, LoggingConfig};
 
 // We need to remove doc comments here, otherwise clap adds them to the help message
 #[allow(missing_docs)]
@@ -14,9 +14,34 @@ pub struct LogArgs {
     ///
     /// The argument can be combined with a level filter to only log messages of a certain level or higher to that destination.
     ///
-    /// Format: destination | level_filter:destination
-    /// * level_filter = "ERROR" | "WARN" | "INFO" | "DEBUG" | "TRACE"
-    /// * destination = "stderr" | "syslog" | "file:path" | "none"
+    /// File paths can contain the placeholders `{pid}`, `{exe}`, `{date}`, `{hostname}` and `{user}`,
+    /// a leading `~` for the home directory, and environment variables like `$VAR`.
+    ///
+    /// A ring buffer keeps the last `capacity` log messages in memory and only writes them to its sink (stderr or a file)
+    /// when an error is logged, the application panics, or the process receives SIGUSR1. File options apply to a file sink, e.g. `ringbuffer:1000:file:/path;format=json`.
+    ///
+    /// A pipe writes log lines to the stdin of a shell command, which is restarted if it exits.
+    /// Quotes inside of the command need to be escaped, e.g. `pipe:logger -t \"my app\"`.
+    ///
+    /// Use `tcp+json://host:port`, `udp+json://host:port` or `unix+json:path` to send one JSON object per log message instead of text lines.
+    ///
+    /// `gelf+udp://host:port` and `gelf+tcp://host:port` send log messages to a GELF input, e.g. of Graylog.
+    /// Use `gelf+udp+gzip://host:port` to compress the messages.
+    ///
+    /// `otlp:url` exports log records to an OpenTelemetry collector with OTLP/HTTP, `otlp+json:url` uses JSON instead of protobuf encoding.
+    ///
+    /// Options can be appended after a `;` as comma separated `key=value` pairs, flags like `truncate` don't need a value.
+    /// Paths and commands containing `:` or `;` can be quoted like `file:"/path/with:colon"`, or escaped like `file:/path/with\:colon`.
+    /// * file: format=text|json, create_dirs, mode=640, truncate, append, lock=fail|wait|suffix|none, fsync=always|errors|never|5s, min_free=1GB, max_size=500MB
+    /// * tcp, udp, unix: format=text|json
+    /// * gelf+udp: compress
+    /// * otlp: encoding=protobuf|json
+    /// * all destinations: include, exclude, include_target, exclude_target to filter by message or target, with a substring like `exclude=healthcheck` or a regular expression like `exclude=/^GET /health/`. A `~` prefix keeps the rest a substring, e.g. `exclude=~/health/`
+    ///
+    /// Format: destination | level_filter:destination, optionally followed by ";options"
+    /// * level_filter = level | level..level | =level, e.g. "DEBUG..INFO" to log only debug and info messages, or "=WARN" to log only warnings
+    /// * level = "OFF" | "ERROR" | "WARN" | "INFO" | "DEBUG" | "TRACE", or an alias like "warning", "fatal", "verbose", or "0" to "5"
+    /// * destination = "stderr" | "syslog" | "file:path" | "fd:number" | "pipe:command" | "memory:name" | "ringbuffer:capacity:sink" | "tcp://host:port" | "udp://host:port" | "unix:path" | "gelf+udp://host:port" | "gelf+tcp://host:port" | "otlp:url" | "none"
     ///
     /// Examples:
     /// * `--log syslog`
@@ -24,7 +49,20 @@ pub struct LogArgs {
     /// * `--log file:/path/to/file`
     /// * `--log INFO:stderr`
     /// * `--log DEBUG:file:/path/to/file`
+    /// * `--log 'DEBUG:file:/path/to/file;format=json,create_dirs,max_size=1GB'`
+    /// * `--log 'file:~/logs/{exe}-{pid}.log'`
+    /// * `--log INFO:fd:3`
+    /// * `--log "INFO:pipe:gzip > /path/to/file.gz"`
     /// * `--log TRACE:syslog`
+    /// * `--log OFF:stderr`
+    /// * `--log DEBUG..INFO:file:/path/to/file`
+    /// * `--log =WARN:stderr`
+    /// * `--log 'INFO:stderr;exclude=/healthcheck|metrics/'`
+    /// * `--log TRACE:ringbuffer:1000:file:/path/to/file`
+    /// * `--log INFO:tcp://localhost:9000`
+    /// * `--log INFO:unix:/path/to/socket`
+    /// * `--log INFO:gelf+udp://localhost:12201`
+    /// * `--log INFO:otlp:http://localhost:4318`
     /// * `--log none`
     #[arg(long, value_parser=parse_destination_config)]
     #[clap(verbatim_doc_comment)]
@@ -38,16 +76,65 @@ fn parse_destination_config(input: &str) -> Result<Option<LogDestinationConfig>,
 impl LogArgs {
     /// Build the [LoggingConfig] defined by the command line arguments from [LogArgs].
     /// If no `--log` argument is given, the default config is returned.
+    ///
+    /// `--log OFF:destination` arguments remove that destination, regardless of its options like `;truncate`. If they are the only `--log` arguments,
+    /// they are applied to the default config, e.g. `--log OFF:stderr` keeps all other default destinations.
     pub fn or_default(&self, default: LoggingConfig) -> LoggingConfig {
         if self.log.is_empty() {
             // No `--log` argument given, use the default config
-            default
+            return default;
+        }
+        let is_off = |config: &LogDestinationConfig| config.level == Some(log::LevelFilter::Off);
+        let only_off = self.log.iter().all(|log| log.as_ref().is_some_and(is_off));
+        // There are `--log` arguments given, but they may be `--log none`.
+        // Let's filter those out. If no non-none are remaining, logging will be disabled.
+        let (off, destinations): (Vec<_>, Vec<_>) =
+            self.log.iter().flatten().cloned().partition(is_off);
+        let destinations = if only_off {
+            default.destinations().to_vec()
         } else {
-            // There are `--log` arguments given, but they may be `--log none`.
-            // Let's filter those out. If no non-none are remaining, logging will be disabled.
-            let destinations = self.log.iter().filter_map(|log| log.clone()).collect();
-            LoggingConfig::new(destinations)
+            destinations
+        };
+        let destinations = destinations
+            .into_iter()
+            .filter(|destination| {
+                !off.iter()
+                    .any(|off| is_same_target(&off.destination, &destination.destination))
+            })
+            .collect();
+        LoggingConfig::new(destinations)
+    }
+}
+
+/// Whether two destinations log to the same place, ignoring their options, e.g. the format of a file or the capacity of a ring buffer.
+/// This is what `--log OFF:destination` turns off.
+fn is_same_target(a: &LogDestination, b: &LogDestination) -> bool {
+    match (a, b) {
+        (LogDestination::File { path: a, .. }, LogDestination::File { path: b, .. })
+        | (LogDestination::Unix { path: a, .. }, LogDestination::Unix { path: b, .. }) => a == b,
+        (LogDestination::Tcp { address: a, .. }, LogDestination::Tcp { address: b, .. })
+        | (LogDestination::Udp { address: a, .. }, LogDestination::Udp { address: b, .. })
+        | (LogDestination::Otlp { endpoint: a, .. }, LogDestination::Otlp { endpoint: b, .. }) => {
+            a == b
+        }
+        (
+            LogDestination::Gelf {
+                address: a,
+                transport: a_transport,
+            },
+            LogDestination::Gelf {
+                address: b,
+                transport: b_transport,
+            },
+        ) => {
+            // UDP and TCP inputs can listen on the same port, so only the compression is an option
+            a == b && std::mem::discriminant(a_transport) == std::mem::discriminant(b_transport)
         }
+        (
+            LogDestination::RingBuffer { sink: a, .. },
+            LogDestination::RingBuffer { sink: b, .. },
+        ) => is_same_target(a, b),
+        (a, b) => a == b,
     }
 }
 
@@ -65,7 +152,7 @@ mod tests {
             assert_eq!(
                 parse_destination_config(""),
                 Err(
-                    "Invalid empty log destination. Choose stderr, syslog, file, or none"
+                    "Invalid empty log destination. Choose stderr, syslog, file, fd, pipe, memory, ringbuffer, tcp, udp, unix, gelf+udp, gelf+tcp, otlp, or none"
                         .to_string()
                 )
             );
@@ -82,7 +169,9 @@ mod tests {
                 parse_destination_config("stderr"),
                 Ok(Some(LogDestinationConfig {
                     destination: LogDestination::Stderr,
-                    level: None
+                    level: None,
+                    most_severe: None,
+                    filters: Vec::new(),
                 }))
             );
         }
@@ -93,7 +182,9 @@ mod tests {
                 parse_destination_config("DEBUG:stderr"),
                 Ok(Some(LogDestinationConfig {
                     destination: LogDestination::Stderr,
-                    level: Some(log::LevelFilter::Debug)
+                    level: Some(log::LevelFilter::Debug),
+                    most_severe: None,
+                    filters: Vec::new(),
                 }))
             );
         }
@@ -101,6 +192,7 @@ mod tests {
 
     mod or_default {
         use crate::LogDestination;
+        use rstest::rstest;
 
         use super::*;
 
@@ -110,6 +202,8 @@ mod tests {
             let default = vec![LogDestinationConfig {
                 destination: LogDestination::Stderr,
                 level: Some(log::LevelFilter::Info),
+                most_severe: None,
+                filters: Vec::new(),
             }];
             let parsed = args.or_default(LoggingConfig::new(default.clone()));
             assert_eq!(default, parsed.destinations());
@@ -121,6 +215,8 @@ mod tests {
             let parsed = args.or_default(LoggingConfig::new(vec![LogDestinationConfig {
                 destination: LogDestination::Stderr,
                 level: Some(log::LevelFilter::Info),
+                most_severe: None,
+                filters: Vec::new(),
             }]));
             assert_eq!(parsed.destinations().len(), 0);
         }
@@ -130,6 +226,8 @@ mod tests {
             let destinations = vec![LogDestinationConfig {
                 destination: LogDestination::Stderr,
                 level: Some(log::LevelFilter::Info),
+                most_severe: None,
+                filters: Vec::new(),
             }];
             let args = LogArgs {
                 log: destinations.iter().cloned().map(Some).collect(),
@@ -144,10 +242,14 @@ mod tests {
                 LogDestinationConfig {
                     destination: LogDestination::Stderr,
                     level: Some(log::LevelFilter::Info),
+                    most_severe: None,
+                    filters: Vec::new(),
                 },
                 LogDestinationConfig {
-                    destination: LogDestination::File(std::path::PathBuf::from("/tmp/logfile")),
+                    destination: LogDestination::file(std::path::PathBuf::from("/tmp/logfile")),
                     level: Some(log::LevelFilter::Debug),
+                    most_severe: None,
+                    filters: Vec::new(),
                 },
             ];
             let args = LogArgs {
@@ -157,11 +259,95 @@ mod tests {
             assert_eq!(destinations, parsed.destinations());
         }
 
+        fn default_config() -> LoggingConfig {
+            LoggingConfig::new(vec![
+                LogDestinationConfig::new(LogDestination::Stderr, Some(log::LevelFilter::Warn)),
+                LogDestinationConfig::new(LogDestination::Syslog, None),
+                LogDestinationConfig::new(LogDestination::file("/tmp/logfile"), None),
+            ])
+        }
+
+        fn args(log: &[&str]) -> LogArgs {
+            LogArgs {
+                log: log
+                    .iter()
+                    .map(|log| parse_destination_config(log).unwrap())
+                    .collect(),
+            }
+        }
+
+        #[test]
+        fn off_flags_disable_default_destinations() {
+            let parsed =
+                args(&["OFF:stderr", "OFF:file:/tmp/logfile"]).or_default(default_config());
+            assert_eq!(
+                vec![LogDestinationConfig::new(LogDestination::Syslog, None)],
+                parsed.destinations()
+            );
+        }
+
+        #[test]
+        fn off_flag_for_destination_not_in_default() {
+            let parsed = args(&["OFF:file:/tmp/other"]).or_default(default_config());
+            assert_eq!(default_config(), parsed);
+        }
+
+        #[rstest]
+        #[case("file:/tmp/logfile;truncate,format=json", "OFF:file:/tmp/logfile")]
+        #[case("file:/tmp/logfile", "OFF:file:/tmp/logfile;lock=fail")]
+        #[case("tcp+json://localhost:9000", "OFF:tcp://localhost:9000")]
+        #[case("unix+json:/run/log.sock", "OFF:unix:/run/log.sock")]
+        #[case("gelf+udp+gzip://graylog:12201", "OFF:gelf+udp://graylog:12201")]
+        #[case("otlp+json:http://localhost:4318", "OFF:otlp:http://localhost:4318")]
+        #[case(
+            "ringbuffer:100:file:/tmp/logfile;truncate",
+            "OFF:ringbuffer:10:file:/tmp/logfile"
+        )]
+        fn off_flag_ignores_options(#[case] default: &str, #[case] off: &str) {
+            let default: LoggingConfig = format!("stderr {default}").parse().unwrap();
+            let parsed = args(&[off]).or_default(default);
+            assert_eq!(
+                vec![LogDestinationConfig::new(LogDestination::Stderr, None)],
+                parsed.destinations()
+            );
+        }
+
+        #[rstest]
+        #[case("gelf+tcp://graylog:12201", "OFF:gelf+udp://graylog:12201")]
+        #[case("tcp://localhost:9000", "OFF:udp://localhost:9000")]
+        #[case("file:/tmp/logfile", "OFF:file:/tmp/other")]
+        fn off_flag_for_other_target(#[case] default: &str, #[case] off: &str) {
+            let default: LoggingConfig = default.parse().unwrap();
+            let parsed = args(&[off]).or_default(default.clone());
+            assert_eq!(default, parsed);
+        }
+
+        #[test]
+        fn off_flag_with_other_flags_replaces_default() {
+            let parsed = args(&["OFF:stderr", "INFO:stderr", "DEBUG:file:/tmp/other"])
+                .or_default(default_config());
+            assert_eq!(
+                vec![LogDestinationConfig::new(
+                    LogDestination::file("/tmp/other"),
+                    Some(log::LevelFilter::Debug)
+                )],
+                parsed.destinations()
+            );
+        }
+
+        #[test]
+        fn off_flag_with_none_flag_disables_logging() {
+            let parsed = args(&["OFF:stderr", "none"]).or_default(default_config());
+            assert_eq!(LoggingConfig::disabled(), parsed);
+        }
+
         #[test]
         fn two_flags_with_one_none_present() {
             let first_flag = LogDestinationConfig {
                 destination: LogDestination::Stderr,
                 level: Some(log::LevelFilter::Info),
+                most_severe: None,
+                filters: Vec::new(),
             };
             let destinations = vec![Some(first_flag.clone()), None];
             let args = LogArgs { log: destinations };
@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use super::parser::ParseError;
+
 /// This enum represents the whole logging configuration,
 /// including all logging destinations and their respective log level filters.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -14,7 +16,13 @@ impl LoggingConfig {
     /// Create a new [LoggingConfig] with the given destinations.
     ///
     /// If the list of destinations is empty, logging is disabled.
+    ///
+    /// # Panics
+    /// If a destination has an empty level range, i.e. its [LogDestinationConfig::most_severe] level is less severe than its [LogDestinationConfig::level].
     pub fn new(destinations: Vec<LogDestinationConfig>) -> Self {
+        for destination in &destinations {
+            destination.assert_level_range();
+        }
         Self { destinations }
     }
 
@@ -29,11 +37,23 @@ impl LoggingConfig {
     pub fn destinations(&self) -> &[LogDestinationConfig] {
         &self.destinations
     }
+
+    /// Add a destination to log to, e.g. to always log to a destination in addition to the ones given with `--log`.
+    ///
+    /// Note that this also enables logging if it was previously disabled.
+    ///
+    /// # Panics
+    /// If the destination has an empty level range, like [LoggingConfig::new].
+    pub fn add_destination(&mut self, destination: LogDestinationConfig) {
+        destination.assert_level_range();
+        self.destinations.push(destination);
+    }
 }
 
 /// Configuration for a log destination, containing the destination and the log level.
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "LogDestinationConfigFields"))]
 pub struct LogDestinationConfig {
     /// The destination to log to.
     pub destination: LogDestination,
@@ -42,6 +62,155 @@ pub struct LogDestinationConfig {
     ///
     /// If `None`, the default level is used.
     pub level: Option<log::LevelFilter>,
+
+    /// Don't log messages more severe than this level to this destination, e.g. `INFO` for `--log DEBUG..INFO:file:/path`
+    /// to log only debug and info messages, or `WARN` for `--log =WARN:stderr` to log only warnings.
+    ///
+    /// If `None`, messages at all levels up to `ERROR` are logged. Must not be less severe than [LogDestinationConfig::level],
+    /// otherwise the range is empty and [LoggingConfig::new] panics.
+    pub most_severe: Option<log::Level>,
+
+    /// Only log messages that match these filters to this destination, e.g. to drop messages of a noisy health check.
+    ///
+    /// A message is logged if it matches none of the [LogFilterKind::Exclude] filters, and at least one of the
+    /// [LogFilterKind::Include] filters if there are any.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub filters: Vec<LogFilter>,
+}
+
+impl LogDestinationConfig {
+    /// Log messages at the given level or higher to the destination, like `--log INFO:stderr`.
+    ///
+    /// If `level` is `None`, the default level is used, like `--log stderr`.
+    pub fn new(destination: LogDestination, level: Option<log::LevelFilter>) -> Self {
+        Self {
+            destination,
+            level,
+            most_severe: None,
+            filters: Vec::new(),
+        }
+    }
+
+    /// Only log messages from one level to another to the destination, like `--log DEBUG..INFO:file:/path`.
+    ///
+    /// The levels can be given in either order. If `from` is `None`, messages from the default level up to `to`
+    /// are logged, like `--log ..INFO:stderr`.
+    pub fn with_level_range(
+        destination: LogDestination,
+        from: Option<log::Level>,
+        to: log::Level,
+    ) -> Self {
+        let (level, most_severe) = match from {
+            Some(from) if from < to => (Some(to), from),
+            Some(from) => (Some(from), to),
+            None => (None, to),
+        };
+        Self {
+            destination,
+            level: level.map(|level| level.to_level_filter()),
+            most_severe: Some(most_severe),
+            filters: Vec::new(),
+        }
+    }
+
+    /// Check that the level range isn't empty. With the default level, this is only known once logging is initialized.
+    fn check_level_range(&self) -> Result<(), String> {
+        match (self.level, self.most_severe) {
+            (Some(level), Some(most_severe)) if level < most_severe.to_level_filter() => {
+                Err(format!(
+                    "Empty level range `{level}..{most_severe}` for log destination `{}`",
+                    self.destination
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn assert_level_range(&self) {
+        if let Err(err) = self.check_level_range() {
+            panic!("{err}");
+        }
+    }
+}
+
+/// The fields of a [LogDestinationConfig], to check the level range when deserializing it.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct LogDestinationConfigFields {
+    destination: LogDestination,
+    level: Option<log::LevelFilter>,
+    most_severe: Option<log::Level>,
+    #[serde(default)]
+    filters: Vec<LogFilter>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<LogDestinationConfigFields> for LogDestinationConfig {
+    type Error = String;
+
+    fn try_from(fields: LogDestinationConfigFields) -> Result<Self, Self::Error> {
+        if let LogDestination::Custom { scheme, .. } = &fields.destination
+            && crate::sink::get_sink(scheme).is_none()
+        {
+            return Err(format!("No log sink registered for `{scheme}`"));
+        }
+        let config = Self {
+            destination: fields.destination,
+            level: fields.level,
+            most_severe: fields.most_severe,
+            filters: fields.filters,
+        };
+        config.check_level_range()?;
+        Ok(config)
+    }
+}
+
+/// A filter on the message or target of log records, e.g. `exclude=/healthcheck/` in a `--log` definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogFilter {
+    /// Whether matching records are kept or dropped.
+    pub kind: LogFilterKind,
+
+    /// The part of the record that is matched.
+    pub field: LogFilterField,
+
+    /// The pattern the field is matched against.
+    pub pattern: LogFilterPattern,
+}
+
+/// Whether a [LogFilter] keeps or drops the records it matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogFilterKind {
+    /// Only log records that match, e.g. `include=/^request/`
+    Include,
+
+    /// Don't log records that match, e.g. `exclude=/healthcheck/`
+    Exclude,
+}
+
+/// The part of a log record that a [LogFilter] is matched against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogFilterField {
+    /// The log message, e.g. `exclude=healthcheck`
+    Message,
+
+    /// The target of the record, usually the module path, e.g. `exclude_target=hyper`
+    Target,
+}
+
+/// The pattern of a [LogFilter].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogFilterPattern {
+    /// Match if the field contains this text, e.g. `exclude=healthcheck`
+    Substring(String),
+
+    /// Match if the field matches this regular expression, e.g. `exclude=/^GET /health/`.
+    /// The syntax is the one of the [regex](https://docs.rs/regex) crate.
+    Regex(String),
 }
 
 /// A destination that can be logged to, e.g. a file or the system log.
@@ -51,11 +220,288 @@ pub enum LogDestination {
     /// Log to stderr
     Stderr,
 
-    /// Log to the file at the given path
-    File(PathBuf),
+    /// Log to the file at the given path.
+    ///
+    /// When logging is initialized, the placeholders `{pid}`, `{exe}`, `{date}`, `{hostname}` and `{user}`,
+    /// a leading `~`, and environment variables like `$VAR` or `${VAR}` in the path are expanded.
+    /// Use `{{` and `}}` for literal braces.
+    File {
+        /// The path of the log file
+        path: PathBuf,
+
+        /// How the log file is opened
+        options: FileOptions,
+    },
 
     /// Log to the system log
     Syslog,
+
+    /// Write log lines to the stdin of a command, e.g. `logger -t myapp` or `gzip > log.gz`.
+    ///
+    /// The command is run by the shell (`sh -c` on unix, `cmd /C` on Windows) and restarted if it exits.
+    Pipe(String),
+
+    /// Log to the in-memory buffer with the given name, see [MemoryBuffer](crate::MemoryBuffer)
+    Memory(String),
+
+    /// Log to an already open file descriptor that was inherited from the parent process, e.g. from a process supervisor.
+    ///
+    /// The file descriptor is reopened through `/dev/fd`, so this is only supported on unix.
+    /// On Linux, sockets can't be reopened that way, use pipes or files instead.
+    Fd(u32),
+
+    /// Keep the last `capacity` log records in memory and only write them to the `sink` when an ERROR is logged,
+    /// the application panics, or (on unix) the process receives SIGUSR1.
+    ///
+    /// This gives detailed context for failures without paying the cost of always writing verbose logs.
+    /// The sink can be [LogDestination::Stderr] or [LogDestination::File].
+    RingBuffer {
+        /// The maximum number of log records to keep in memory
+        capacity: usize,
+
+        /// Where to write the log records to when a trigger happens
+        sink: Box<LogDestination>,
+    },
+
+    /// Send log lines to a TCP server at the given `host:port` address, separated by newlines.
+    ///
+    /// If the connection breaks, it is re-established with exponential backoff.
+    /// Log lines logged while there is no connection are dropped.
+    Tcp {
+        /// The `host:port` address to connect to
+        address: String,
+
+        /// The format of the log lines
+        format: LogFormat,
+    },
+
+    /// Send each log line as a UDP datagram to the given `host:port` address.
+    Udp {
+        /// The `host:port` address to send to
+        address: String,
+
+        /// The format of the log lines
+        format: LogFormat,
+    },
+
+    /// Send log lines to the unix domain socket at the given path, e.g. a socket of a local log shipper.
+    ///
+    /// Both stream sockets (newline separated lines) and datagram sockets (one datagram per line) are supported.
+    /// Like for [LogDestination::Tcp], the connection is re-established with exponential backoff if it breaks.
+    /// This is only supported on unix.
+    Unix {
+        /// The path of the socket
+        path: PathBuf,
+
+        /// The format of the log lines
+        format: LogFormat,
+    },
+
+    /// Send log records in the [Graylog Extended Log Format](https://go2docs.graylog.org/current/getting_in_log_data/gelf.html)
+    /// to the given `host:port` address.
+    ///
+    /// Besides the message and level, the target, source file, source line and key-values of the log record
+    /// are sent as additional fields, e.g. `_target` or `_file`.
+    Gelf {
+        /// The `host:port` address of the GELF input
+        address: String,
+
+        /// How the GELF messages are sent
+        transport: GelfTransport,
+    },
+
+    /// Export log records as [OTLP](https://opentelemetry.io/docs/specs/otlp/) log records over HTTP
+    /// to an OpenTelemetry collector, e.g. `http://localhost:4318`.
+    ///
+    /// If the endpoint has no path, the default `/v1/logs` path is used. Records are exported in batches
+    /// on a background thread, with the process name as `service.name` resource attribute.
+    /// This requires the `otlp` feature, without it initializing logging fails.
+    Otlp {
+        /// The URL of the OTLP/HTTP endpoint
+        endpoint: String,
+
+        /// How the log records are encoded
+        encoding: OtlpEncoding,
+    },
+
+    /// Log to a custom log destination that was registered with [register_sink](crate::register_sink).
+    ///
+    /// Create it with [LogDestination::custom], which checks that the scheme is registered.
+    #[non_exhaustive]
+    Custom {
+        /// The scheme the [LogSink](crate::LogSink) was registered for, in lowercase
+        scheme: String,
+
+        /// The argument following the scheme, e.g. `topic` in `--log INFO:myqueue:topic`.
+        /// Empty if there was nothing after the scheme.
+        argument: String,
+    },
+}
+
+impl LogDestination {
+    /// Log to the file at the given path, with default [FileOptions].
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::File {
+            path: path.into(),
+            options: FileOptions::default(),
+        }
+    }
+
+    /// Log to the custom log destination registered for `scheme` with [register_sink](crate::register_sink),
+    /// like `--log scheme:argument`. Pass an empty `argument` for `--log scheme`.
+    ///
+    /// Fails if no [LogSink](crate::LogSink) is registered for the scheme, or if it rejects the argument.
+    pub fn custom(scheme: &str, argument: impl Into<String>) -> Result<Self, ParseError> {
+        let Some(sink) = crate::sink::get_sink(scheme) else {
+            return Err(ParseError::new(format!(
+                "No log sink registered for `{scheme}`. Register it with `register_sink` first"
+            )));
+        };
+        let argument = argument.into();
+        sink.validate(&argument).map_err(ParseError::new)?;
+        Ok(Self::Custom {
+            scheme: scheme.to_ascii_lowercase(),
+            argument,
+        })
+    }
+}
+
+/// Options for [LogDestination::File].
+///
+/// By default, the log file is appended to, or created with the default permissions if it doesn't exist.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileOptions {
+    /// The format of the log lines
+    pub format: LogFormat,
+
+    /// Create missing parent directories of the log file.
+    pub create_dirs: bool,
+
+    /// On unix, the permissions of the log file if it gets created, e.g. `0o600` to make it only readable by the owner.
+    ///
+    /// If `None`, the default permissions (usually `0o666`) restricted by the umask are used.
+    /// The permissions of an existing log file are not changed.
+    pub mode: Option<u32>,
+
+    /// Truncate the log file if it already exists, instead of appending to it.
+    pub truncate: bool,
+
+    /// Whether to take an exclusive advisory lock on the log file, so that concurrently running processes
+    /// don't write to the same log file.
+    pub lock: FileLock,
+
+    /// When to flush written log records to the disk with `fsync`, so they survive a crash of the operating system or a power loss.
+    pub fsync: FsyncPolicy,
+
+    /// Only log WARN and ERROR records while the disk has less than this many bytes of free space.
+    pub min_free_space: Option<u64>,
+
+    /// Only log WARN and ERROR records while the log file is larger than this many bytes.
+    pub max_size: Option<u64>,
+}
+
+/// What to do if another process holds the lock on a log file, see [FileOptions::lock].
+///
+/// The lock is an advisory lock (`flock` on unix), so it only protects against other processes that also lock the file.
+/// It is held until the process exits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FileLock {
+    /// Don't lock the log file
+    #[default]
+    Disabled,
+
+    /// Fail to initialize the log destination if the log file is locked
+    Fail,
+
+    /// Wait until the other process releases the lock
+    Wait,
+
+    /// Log to the first unlocked file of `name.1.ext`, `name.2.ext`, ... instead
+    Suffix,
+}
+
+/// When log records written to a file are flushed to the disk, see [FileOptions::fsync].
+///
+/// Log records are always handed to the operating system right away, so they survive a crash of the process either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FsyncPolicy {
+    /// Leave it to the operating system when to write log records to the disk
+    #[default]
+    Never,
+
+    /// Sync after every log record
+    Always,
+
+    /// Sync after every ERROR log record, including the records logged before it
+    Errors,
+
+    /// Sync in the background at the given interval, if anything was logged since the last sync
+    Periodic(std::time::Duration),
+}
+
+/// The format of log lines written to a log destination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogFormat {
+    /// Human readable text, e.g. `[2024-01-01T00:00:00Z INFO my_app] Some message`
+    #[default]
+    Text,
+
+    /// One JSON object per line, with `timestamp`, `level`, `target` and `message` fields,
+    /// and a `fields` object containing the key-values of the log record if there are any.
+    Json,
+}
+
+/// How messages are sent to a [LogDestination::Gelf] destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GelfTransport {
+    /// One UDP datagram per message. Large messages are split into GELF chunks.
+    Udp {
+        /// Compress messages with gzip before sending them
+        compress: bool,
+    },
+
+    /// A TCP connection with null byte separated messages, reconnecting with exponential backoff if it breaks.
+    Tcp,
+}
+
+/// The encoding of requests to a [LogDestination::Otlp] destination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OtlpEncoding {
+    /// OTLP/HTTP with binary protobuf encoding
+    #[default]
+    Protobuf,
+
+    /// OTLP/HTTP with JSON encoding
+    Json,
+}
+
+/// Options for [init_logging!](crate::init_logging) that are chosen by the application developer
+/// instead of by the user on the command line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InitOptions {
+    /// Whether to install a panic hook that logs panics to the configured log destinations.
+    pub panic_hook: PanicHook,
+}
+
+/// Configuration for the panic hook installed by [init_logging!](crate::init_logging).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PanicHook {
+    /// Don't install a panic hook. Panics are only reported by the default panic hook, i.e. on stderr.
+    #[default]
+    Disabled,
+
+    /// Log panic messages and their location at ERROR level to all configured log destinations,
+    /// then delegate to the previously installed panic hook.
+    Enabled,
+
+    /// Like [PanicHook::Enabled], but also capture a backtrace and add it to the log message.
+    EnabledWithBacktrace,
 }
 
 #[cfg(test)]
@@ -74,10 +520,14 @@ mod tests {
             LogDestinationConfig {
                 destination: LogDestination::Stderr,
                 level: Some(log::LevelFilter::Info),
+                most_severe: None,
+                filters: Vec::new(),
             },
             LogDestinationConfig {
-                destination: LogDestination::File(PathBuf::from("/tmp/logfile")),
+                destination: LogDestination::file(PathBuf::from("/tmp/logfile")),
                 level: Some(log::LevelFilter::Debug),
+                most_severe: None,
+                filters: Vec::new(),
             },
         ]);
         assert_eq!(
@@ -85,16 +535,130 @@ mod tests {
                 LogDestinationConfig {
                     destination: LogDestination::Stderr,
                     level: Some(log::LevelFilter::Info),
+                    most_severe: None,
+                    filters: Vec::new(),
                 },
                 LogDestinationConfig {
-                    destination: LogDestination::File(PathBuf::from("/tmp/logfile")),
+                    destination: LogDestination::file(PathBuf::from("/tmp/logfile")),
                     level: Some(log::LevelFilter::Debug),
+                    most_severe: None,
+                    filters: Vec::new(),
                 },
             ],
             config.destinations()
         );
     }
 
+    #[test]
+    fn add_destination() {
+        let mut config = LoggingConfig::disabled();
+        config.add_destination(LogDestinationConfig {
+            destination: LogDestination::Stderr,
+            level: Some(log::LevelFilter::Info),
+            most_severe: None,
+            filters: Vec::new(),
+        });
+        assert_eq!(
+            vec![LogDestinationConfig {
+                destination: LogDestination::Stderr,
+                level: Some(log::LevelFilter::Info),
+                most_severe: None,
+                filters: Vec::new(),
+            }],
+            config.destinations()
+        );
+    }
+
+    struct TopicSink;
+
+    impl crate::LogSink for TopicSink {
+        fn validate(&self, topic: &str) -> Result<(), String> {
+            if topic.is_empty() {
+                return Err("The config-tests-queue sink requires a topic".to_string());
+            }
+            Ok(())
+        }
+
+        fn build(&self, _topic: &str) -> anyhow::Result<Box<dyn log::Log>> {
+            anyhow::bail!("The config-tests-queue sink can't be built")
+        }
+    }
+
+    #[test]
+    fn custom_destination() {
+        crate::register_sink("config-tests-queue", TopicSink);
+        assert_eq!(
+            LogDestination::Custom {
+                scheme: "config-tests-queue".to_string(),
+                argument: "topic".to_string(),
+            },
+            LogDestination::custom("Config-Tests-Queue", "topic").unwrap()
+        );
+        assert_eq!(
+            "The config-tests-queue sink requires a topic",
+            LogDestination::custom("config-tests-queue", "")
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "No log sink registered for `config-tests-unregistered`. Register it with `register_sink` first",
+            LogDestination::custom("config-tests-unregistered", "topic")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn level_range() {
+        for (from, to) in [
+            (Some(log::Level::Debug), log::Level::Info),
+            (Some(log::Level::Info), log::Level::Debug),
+        ] {
+            let config = LogDestinationConfig::with_level_range(LogDestination::Stderr, from, to);
+            assert_eq!(Some(log::LevelFilter::Debug), config.level);
+            assert_eq!(Some(log::Level::Info), config.most_severe);
+        }
+        let config =
+            LogDestinationConfig::with_level_range(LogDestination::Stderr, None, log::Level::Warn);
+        assert_eq!(None, config.level);
+        assert_eq!(Some(log::Level::Warn), config.most_severe);
+    }
+
+    #[test]
+    #[should_panic(expected = "Empty level range `ERROR..INFO` for log destination `stderr`")]
+    fn logging_config_with_empty_level_range() {
+        LoggingConfig::new(vec![LogDestinationConfig {
+            destination: LogDestination::Stderr,
+            level: Some(log::LevelFilter::Error),
+            most_severe: Some(log::Level::Info),
+            filters: Vec::new(),
+        }]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Empty level range `ERROR..INFO` for log destination `stderr`")]
+    fn add_destination_with_empty_level_range() {
+        LoggingConfig::disabled().add_destination(LogDestinationConfig {
+            destination: LogDestination::Stderr,
+            level: Some(log::LevelFilter::Error),
+            most_severe: Some(log::Level::Info),
+            filters: Vec::new(),
+        });
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn empty_level_range_isnt_deserialized() {
+        let config = LogDestinationConfig {
+            destination: LogDestination::Stderr,
+            level: Some(log::LevelFilter::Error),
+            most_severe: Some(log::Level::Info),
+            filters: Vec::new(),
+        };
+        let bytes = postcard::to_stdvec(&config).unwrap();
+        assert!(postcard::from_bytes::<LogDestinationConfig>(&bytes).is_err());
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn logging_config_round_trips_through_postcard() {
@@ -102,14 +666,20 @@ mod tests {
             LogDestinationConfig {
                 destination: LogDestination::Syslog,
                 level: Some(log::LevelFilter::Info),
+                most_severe: None,
+                filters: Vec::new(),
             },
             LogDestinationConfig {
-                destination: LogDestination::File(PathBuf::from("/tmp/x.log")),
+                destination: LogDestination::file(PathBuf::from("/tmp/x.log")),
                 level: None,
+                most_severe: None,
+                filters: Vec::new(),
             },
             LogDestinationConfig {
                 destination: LogDestination::Stderr,
                 level: Some(log::LevelFilter::Warn),
+                most_severe: None,
+                filters: Vec::new(),
             },
         ]);
         let bytes = postcard::to_stdvec(&cfg).unwrap();
@@ -1,33 +1,73 @@
 use std::io::IsTerminal as _;
+#[cfg(feature = "tracing")]
+use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Context as _, Result, anyhow};
 use fern::{
     Dispatch, FormatCallback,
     colors::{Color, ColoredLevelConfig},
 };
 
-use super::config::{LogDestination, LogDestinationConfig, LoggingConfig};
+use super::config::{InitOptions, LogDestination, LogDestinationConfig, LogFormat, LoggingConfig};
+use super::file::{FileLockedError, FileLogger, open_log_file};
+use super::filter::FilteringLogger;
+use super::gelf::GelfLogger;
+use super::line_logger::{BackgroundLineWriter, LineLogger};
+use super::memory::MemoryLogger;
+use super::network::{ReconnectingLineWriter, UdpLineWriter};
+#[cfg(feature = "otlp")]
+use super::otlp::OtlpLogger;
+use super::path_template::expand_path;
+use super::pipe::PipeLineWriter;
+use super::ring_buffer::RingBufferLogger;
+use super::sink::get_sink;
+use super::utils::collect_key_values;
 
 /// Initialize logging with the given configuration and default level.
 ///
 /// # Arguments
 /// * `config` - The logging configuration to use.
 /// * `default_level` - The default log level to use if a destination was specified without a log level filter.
+/// * `options` - Optional [InitOptions], e.g. to install a panic hook. If omitted, [InitOptions::default()] is used.
+///
+/// Returns a [LoggingGuard] that flushes all log destinations when it's dropped. Hold on to it until the end of `main`,
+/// so that records that network destinations haven't sent yet aren't lost when the process exits.
+///
+/// Log destinations that can't be opened, e.g. `syslog` on a system without a syslog daemon, are skipped.
+///
+/// # Panics
+/// If a log file with the `lock=fail` option is locked by another process.
 ///
 /// # Example
 /// ```rust
 #[doc = include_str!("../examples/simple_cli.rs")]
 /// ```
+///
+/// To also log panics to the configured destinations, pass in [InitOptions]:
+/// ```rust
+/// use clap_logflag::{InitOptions, LoggingConfig, PanicHook};
+/// use log::LevelFilter;
+///
+/// let _logging = clap_logflag::init_logging!(
+///     LoggingConfig::disabled(),
+///     LevelFilter::Info,
+///     InitOptions {
+///         panic_hook: PanicHook::EnabledWithBacktrace,
+///     },
+/// );
+/// ```
 #[macro_export]
 macro_rules! init_logging {
-    ($config:expr, $default_level:expr $(,)?) => {{
+    ($config:expr, $default_level:expr $(,)?) => {{ $crate::init_logging!($config, $default_level, $crate::InitOptions::default()) }};
+    ($config:expr, $default_level:expr, $options:expr $(,)?) => {{
         $crate::_init_logging(
             $config,
             $default_level,
+            $options,
             option_env!("CARGO_BIN_NAME"),
             env!("CARGO_CRATE_NAME"),
         )
-        .expect("Failed to initialize logging");
+        .expect("Failed to initialize logging")
     }};
 }
 
@@ -35,37 +75,98 @@ macro_rules! init_logging {
 pub fn _init_logging(
     config: LoggingConfig,
     default_level: log::LevelFilter,
+    options: InitOptions,
     cargo_bin_name: Option<&str>,
     cargo_crate_name: &str,
-) -> Result<()> {
-    if let Some(main_logger) =
-        build_main_logger(config, default_level, cargo_bin_name, cargo_crate_name)
-    {
-        main_logger.apply()?;
+) -> Result<LoggingGuard> {
+    crate::child::set_active(&config, default_level);
+    let Some(main_logger) =
+        build_main_logger(config, default_level, cargo_bin_name, cargo_crate_name)?
+    else {
+        // Logging is disabled, so there is nothing to flush
+        return Ok(LoggingGuard { logger: None });
+    };
+    main_logger.apply()?;
+    // Only install the panic hook if logging is enabled, otherwise there is nowhere to log panics to.
+    crate::panic_hook::install(options.panic_hook);
+    Ok(LoggingGuard {
+        logger: Some(FlushTarget::Global),
+    })
+}
+
+/// Flushes all log destinations when it's dropped, see [init_logging!].
+///
+/// Some log destinations, e.g. `tcp://host:port`, send records on a background thread. Flushing waits a few seconds at most
+/// until they're sent. Without it, records logged right before the process exits are lost.
+/// Note that [std::process::exit] doesn't drop the guard, so flush with `log::logger().flush()` before calling it.
+#[must_use = "Dropping the guard right away flushes the log destinations too early. Assign it to a variable like `_logging` instead"]
+pub struct LoggingGuard {
+    logger: Option<FlushTarget>,
+}
+
+enum FlushTarget {
+    /// The global logger of the `log` crate, set by [init_logging!]
+    Global,
+    /// The logger of a [LogflagLayer](crate::LogflagLayer)
+    #[cfg(feature = "tracing")]
+    Logger(Arc<dyn log::Log>),
+}
+
+impl LoggingGuard {
+    #[cfg(feature = "tracing")]
+    pub(crate) fn new(logger: Option<Arc<dyn log::Log>>) -> Self {
+        Self {
+            logger: logger.map(FlushTarget::Logger),
+        }
     }
-    Ok(())
 }
 
-fn build_main_logger(
+impl std::fmt::Debug for LoggingGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoggingGuard").finish_non_exhaustive()
+    }
+}
+
+impl Drop for LoggingGuard {
+    fn drop(&mut self) {
+        match &self.logger {
+            Some(FlushTarget::Global) => log::logger().flush(),
+            #[cfg(feature = "tracing")]
+            Some(FlushTarget::Logger(logger)) => logger.flush(),
+            None => {}
+        }
+    }
+}
+
+pub fn build_main_logger(
     config: LoggingConfig,
     default_level: log::LevelFilter,
     cargo_bin_name: Option<&str>,
     cargo_crate_name: &str,
-) -> Option<Dispatch> {
+) -> Result<Option<Dispatch>> {
     if config.destinations().is_empty() {
         // Logging is disabled
-        return None;
+        return Ok(None);
     }
 
     let process_name = process_name(cargo_bin_name, cargo_crate_name);
 
     let mut main_logger = Dispatch::new();
     for destination in config.destinations() {
-        if let Ok(logger) = build_logger(destination, default_level, process_name.clone()) {
-            main_logger = main_logger.chain(logger);
+        if destination.level.unwrap_or(default_level) == log::LevelFilter::Off {
+            // Don't open files or connections for destinations that are turned off, e.g. with `--log OFF:stderr`
+            continue;
+        }
+        match build_logger(destination, default_level, process_name.clone()) {
+            Ok(logger) => main_logger = main_logger.chain(logger),
+            Err(err) if err.chain().any(|cause| cause.is::<FileLockedError>()) => {
+                return Err(err.context(format!("Failed to open log destination `{destination}`")));
+            }
+            // Skip destinations that can't be opened, so the application still starts and logs to the other destinations
+            Err(_) => {}
         }
     }
-    Some(main_logger)
+    Ok(Some(main_logger))
 }
 
 fn build_logger(
@@ -73,7 +174,10 @@ fn build_logger(
     default_level: log::LevelFilter,
     process_name: String,
 ) -> Result<Dispatch> {
-    let logger = Dispatch::new().level(config.level.unwrap_or(default_level));
+    let mut logger = Dispatch::new().level(config.level.unwrap_or(default_level));
+    if let Some(most_severe) = config.most_severe {
+        logger = logger.filter(move |metadata| metadata.level() >= most_severe);
+    }
     let logger = match &config.destination {
         LogDestination::Stderr => {
             if std::io::stderr().is_terminal() {
@@ -82,9 +186,17 @@ fn build_logger(
                 logger.format(log_formatter_file()).chain(std::io::stderr())
             }
         }
-        LogDestination::File(path) => logger
-            .format(log_formatter_file())
-            .chain(fern::log_file(path)?),
+        LogDestination::File { path, options } => {
+            let path = expand_path(path, &process_name)?;
+            let file_logger: Box<dyn log::Log> = Box::new(FileLogger::new(
+                open_log_file(&path, options)?,
+                &path,
+                options,
+            ));
+            logger
+                .format(log_formatter(options.format))
+                .chain(file_logger)
+        }
         LogDestination::Syslog => {
             let syslog_formatter = syslog::Formatter3164 {
                 facility: syslog::Facility::LOG_USER,
@@ -94,8 +206,114 @@ fn build_logger(
             };
             logger.chain(syslog::unix(syslog_formatter)?)
         }
+        LogDestination::Fd(fd) => logger.format(log_formatter_file()).chain(open_fd(*fd)?),
+        LogDestination::Pipe(command) => logger
+            .format(log_formatter_file())
+            .chain(LineLogger::boxed(PipeLineWriter::new(command)?)),
+        LogDestination::Memory(name) => {
+            let memory_logger: Box<dyn log::Log> = Box::new(MemoryLogger::new(name));
+            logger.format(log_formatter_file()).chain(memory_logger)
+        }
+        LogDestination::RingBuffer { capacity, sink } => {
+            let sink = match sink.as_ref() {
+                LogDestination::File { path, options } => LogDestination::File {
+                    path: expand_path(path, &process_name)?,
+                    options: options.clone(),
+                },
+                sink => sink.clone(),
+            };
+            let format = match &sink {
+                LogDestination::File { options, .. } => options.format,
+                _ => LogFormat::Text,
+            };
+            let ring_buffer_logger: Box<dyn log::Log> =
+                Box::new(RingBufferLogger::new(*capacity, &sink)?);
+            logger
+                .format(log_formatter(format))
+                .chain(ring_buffer_logger)
+        }
+        LogDestination::Tcp { address, format } => {
+            logger
+                .format(log_formatter(*format))
+                .chain(LineLogger::boxed(BackgroundLineWriter::new(
+                    ReconnectingLineWriter::tcp(address),
+                )))
+        }
+        LogDestination::Udp { address, format } => logger
+            .format(log_formatter(*format))
+            .chain(LineLogger::boxed(UdpLineWriter::new(address)?)),
+        #[cfg(unix)]
+        LogDestination::Unix { path, format } => {
+            logger
+                .format(log_formatter(*format))
+                .chain(LineLogger::boxed(BackgroundLineWriter::new(
+                    ReconnectingLineWriter::unix(path),
+                )))
+        }
+        #[cfg(not(unix))]
+        LogDestination::Unix { .. } => {
+            return Err(anyhow!(
+                "Unix domain socket log destinations are only supported on unix"
+            ));
+        }
+        LogDestination::Gelf { address, transport } => {
+            // GELF messages are built from the unformatted record, so don't set a formatter
+            let gelf_logger: Box<dyn log::Log> = Box::new(GelfLogger::new(address, *transport)?);
+            logger.chain(gelf_logger)
+        }
+        #[cfg(feature = "otlp")]
+        LogDestination::Otlp { endpoint, encoding } => {
+            // OTLP log records are built from the unformatted record, so don't set a formatter
+            let otlp_logger: Box<dyn log::Log> =
+                Box::new(OtlpLogger::new(endpoint, *encoding, process_name));
+            logger.chain(otlp_logger)
+        }
+        #[cfg(not(feature = "otlp"))]
+        LogDestination::Otlp { .. } => {
+            return Err(anyhow!(
+                "OTLP log destinations require the `otlp` feature of clap-logflag"
+            ));
+        }
+        LogDestination::Custom { scheme, argument } => {
+            let sink = get_sink(scheme)
+                .ok_or_else(|| anyhow!("No log sink registered for scheme `{scheme}`"))?;
+            logger.chain(sink.build(argument)?)
+        }
     };
-    Ok(logger)
+    if config.filters.is_empty() {
+        return Ok(logger);
+    }
+    // fern's filters only see the metadata, but we need the message. So wrap the destination in a logger that sees the whole record.
+    let (level, logger) = logger.into_log();
+    let filtering_logger: Box<dyn log::Log> =
+        Box::new(FilteringLogger::new(&config.filters, logger)?);
+    Ok(Dispatch::new().level(level).chain(filtering_logger))
+}
+
+/// Open an inherited file descriptor for writing. Without unsafe code, we can't take ownership of the file descriptor,
+/// so we reopen it through `/dev/fd`. Append mode makes sure we don't overwrite what others wrote to the same file.
+#[cfg(unix)]
+fn open_fd(fd: u32) -> Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(format!("/dev/fd/{fd}"))
+        .with_context(|| format!("Failed to open file descriptor {fd}"))
+}
+
+#[cfg(not(unix))]
+fn open_fd(_fd: u32) -> Result<std::fs::File> {
+    Err(anyhow!(
+        "File descriptor log destinations are only supported on unix"
+    ))
+}
+
+type Formatter = Box<dyn Fn(FormatCallback, &std::fmt::Arguments, &log::Record) + Sync + Send>;
+
+fn log_formatter(format: LogFormat) -> Formatter {
+    match format {
+        LogFormat::Text => Box::new(log_formatter_file()),
+        LogFormat::Json => Box::new(log_formatter_json()),
+    }
 }
 
 fn log_formatter_tty() -> impl Fn(FormatCallback, &std::fmt::Arguments, &log::Record) {
@@ -128,6 +346,48 @@ fn log_formatter_file() -> impl Fn(FormatCallback, &std::fmt::Arguments, &log::R
     }
 }
 
+fn log_formatter_json() -> impl Fn(FormatCallback, &std::fmt::Arguments, &log::Record) {
+    move |out: FormatCallback, message: &std::fmt::Arguments, record: &log::Record| {
+        let mut line = serde_json::json!({
+            "timestamp": humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+            "level": record.level().as_str(),
+            "target": record.target(),
+            "message": message.to_string(),
+        });
+        let JsonFieldCollector(fields) = collect_key_values(record);
+        if !fields.is_empty() {
+            line["fields"] = serde_json::Value::Object(fields);
+        }
+        out.finish(format_args!("{line}"))
+    }
+}
+
+/// Collects the key-values of a log record as JSON values, keeping numbers and booleans as such.
+#[derive(Default)]
+pub struct JsonFieldCollector(pub serde_json::Map<String, serde_json::Value>);
+
+impl<'kvs> log::kv::VisitSource<'kvs> for JsonFieldCollector {
+    fn visit_pair(
+        &mut self,
+        key: log::kv::Key<'kvs>,
+        value: log::kv::Value<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        let value = if let Some(value) = value.to_i64() {
+            value.into()
+        } else if let Some(value) = value.to_u64() {
+            value.into()
+        } else if let Some(value) = value.to_f64() {
+            value.into()
+        } else if let Some(value) = value.to_bool() {
+            value.into()
+        } else {
+            value.to_string().into()
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
 /// Get a process name. Try in the following order:
 /// 1. Try getting it from argv, i.e. the name of the currently running executable
 /// 2. Try getting it from the `CARGO_BIN_NAME` environment variable
@@ -157,6 +417,7 @@ fn exe_name() -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::FileOptions;
     use log::LevelFilter;
     use predicates::Predicate;
     use rstest::rstest;
@@ -193,6 +454,8 @@ mod tests {
         let config = LogDestinationConfig {
             destination: LogDestination::Stderr,
             level: None,
+            most_severe: None,
+            filters: Vec::new(),
         };
         let logger = build_logger(&config, level, "process_name".to_string())
             .unwrap()
@@ -214,8 +477,10 @@ mod tests {
         let tempdir = assert_fs::TempDir::new().unwrap();
         let file = tempdir.path().join("logfile");
         let config = LogDestinationConfig {
-            destination: LogDestination::File(file),
+            destination: LogDestination::file(file),
             level: None,
+            most_severe: None,
+            filters: Vec::new(),
         };
         let logger = build_logger(&config, level, "process_name".to_string())
             .unwrap()
@@ -237,8 +502,10 @@ mod tests {
         let tempdir = assert_fs::TempDir::new().unwrap();
         let file = tempdir.path().join("logfile");
         let config = LogDestinationConfig {
-            destination: LogDestination::File(file.clone()),
+            destination: LogDestination::file(file.clone()),
             level: None,
+            most_severe: None,
+            filters: Vec::new(),
         };
         let (actual_level, logger) = build_logger(&config, level, "process_name".to_string())
             .unwrap()
@@ -267,6 +534,365 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_file_logger_with_path_template() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let config = LogDestinationConfig {
+            destination: LogDestination::file(tempdir.path().join("{exe}-{pid}.log")),
+            level: None,
+            most_severe: None,
+            filters: Vec::new(),
+        };
+        let (_, logger) = build_logger(&config, LevelFilter::Info, "my-app".to_string())
+            .unwrap()
+            .into_log();
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("test log message"))
+                .level(log::Level::Info)
+                .target("my-test")
+                .build(),
+        );
+        logger.flush();
+
+        let file = tempdir
+            .path()
+            .join(format!("my-app-{}.log", std::process::id()));
+        assert!(
+            std::fs::read_to_string(&file)
+                .unwrap()
+                .ends_with("test log message\n")
+        );
+    }
+
+    #[test]
+    fn test_build_ring_buffer_logger_with_json_file() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let file = tempdir.path().join("logfile");
+        let config = LogDestinationConfig {
+            destination: LogDestination::RingBuffer {
+                capacity: 10,
+                sink: Box::new(LogDestination::File {
+                    path: file.clone(),
+                    options: FileOptions {
+                        format: LogFormat::Json,
+                        ..FileOptions::default()
+                    },
+                }),
+            },
+            level: None,
+            most_severe: None,
+            filters: Vec::new(),
+        };
+        let (_, logger) = build_logger(&config, LevelFilter::Info, "my-app".to_string())
+            .unwrap()
+            .into_log();
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("test log message"))
+                .level(log::Level::Error)
+                .target("my-test")
+                .build(),
+        );
+
+        let line: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!("test log message", line["message"]);
+        assert_eq!("ERROR", line["level"]);
+    }
+
+    #[test]
+    fn test_log_formatter_memory() {
+        let config = LogDestinationConfig {
+            destination: LogDestination::Memory(
+                "fern::tests::test_log_formatter_memory".to_string(),
+            ),
+            level: None,
+            most_severe: None,
+            filters: Vec::new(),
+        };
+        let (_, logger) = build_logger(&config, LevelFilter::Info, "process_name".to_string())
+            .unwrap()
+            .into_log();
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("test log message"))
+                .level(log::Level::Warn)
+                .target("my-test")
+                .build(),
+        );
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("filtered log message"))
+                .level(log::Level::Debug)
+                .target("my-test")
+                .build(),
+        );
+
+        let buffer = crate::MemoryBuffer::get("fern::tests::test_log_formatter_memory");
+        let lines = buffer.lines();
+        assert_eq!(1, lines.len(), "lines: {lines:?}");
+        let expected_log_regex =
+            format!(r"^\[{} WARN my-test\] test log message$", timestamp_regex());
+        buffer.assert_logged(log::Level::Warn, &expected_log_regex);
+    }
+
+    #[test]
+    fn test_build_custom_logger() {
+        struct TestSink;
+
+        impl crate::LogSink for TestSink {
+            fn build(&self, argument: &str) -> Result<Box<dyn log::Log>> {
+                // Reuse the memory logger to check that the sink gets the argument and unformatted records
+                Ok(Box::new(MemoryLogger::new(argument)))
+            }
+        }
+
+        crate::register_sink("fern-tests-sink", TestSink);
+        let config = LogDestinationConfig {
+            destination: LogDestination::Custom {
+                scheme: "fern-tests-sink".to_string(),
+                argument: "fern::tests::test_build_custom_logger".to_string(),
+            },
+            level: None,
+            most_severe: None,
+            filters: Vec::new(),
+        };
+        let (_, logger) = build_logger(&config, LevelFilter::Info, "process_name".to_string())
+            .unwrap()
+            .into_log();
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("test log message"))
+                .level(log::Level::Info)
+                .target("my-test")
+                .build(),
+        );
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("filtered log message"))
+                .level(log::Level::Debug)
+                .target("my-test")
+                .build(),
+        );
+
+        assert_eq!(
+            vec!["test log message".to_string()],
+            crate::MemoryBuffer::get("fern::tests::test_build_custom_logger").lines()
+        );
+    }
+
+    #[test]
+    fn test_build_unregistered_custom_logger() {
+        let config = LogDestinationConfig {
+            destination: LogDestination::Custom {
+                scheme: "fern-tests-unregistered".to_string(),
+                argument: String::new(),
+            },
+            level: None,
+            most_severe: None,
+            filters: Vec::new(),
+        };
+        let error = build_logger(&config, LevelFilter::Info, "process_name".to_string())
+            .err()
+            .unwrap();
+        assert_eq!(
+            "No log sink registered for scheme `fern-tests-unregistered`",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn test_log_formatter_json() {
+        // Use the memory logger to capture the formatted output
+        let (_, logger) = Dispatch::new()
+            .format(log_formatter(LogFormat::Json))
+            .chain(
+                Box::new(MemoryLogger::new("fern::tests::test_log_formatter_json"))
+                    as Box<dyn log::Log>,
+            )
+            .into_log();
+        let key_values: [(&str, log::kv::Value); 3] = [
+            ("user", "alice".into()),
+            ("attempt", 3.into()),
+            ("ok", true.into()),
+        ];
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("test \"log\" message"))
+                .level(log::Level::Warn)
+                .target("my-test")
+                .key_values(&key_values)
+                .build(),
+        );
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("without fields"))
+                .level(log::Level::Info)
+                .target("my-test")
+                .build(),
+        );
+
+        let lines = crate::MemoryBuffer::get("fern::tests::test_log_formatter_json").lines();
+        assert_eq!(2, lines.len());
+        let line: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert!(
+            predicates::str::is_match(format!("^{}$", timestamp_regex()))
+                .unwrap()
+                .eval(line["timestamp"].as_str().unwrap())
+        );
+        assert_eq!(
+            serde_json::json!({
+                "timestamp": line["timestamp"],
+                "level": "WARN",
+                "target": "my-test",
+                "message": "test \"log\" message",
+                "fields": {"user": "alice", "attempt": 3, "ok": true},
+            }),
+            line
+        );
+        let line: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "timestamp": line["timestamp"],
+                "level": "INFO",
+                "target": "my-test",
+                "message": "without fields",
+            }),
+            line
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_build_fd_logger() {
+        use std::os::fd::AsRawFd as _;
+
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("logfile");
+        std::fs::write(&path, "existing line\n").unwrap();
+        let file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        let config = LogDestinationConfig {
+            destination: LogDestination::Fd(file.as_raw_fd() as u32),
+            level: None,
+            most_severe: None,
+            filters: Vec::new(),
+        };
+        let (_, logger) = build_logger(&config, LevelFilter::Info, "process_name".to_string())
+            .unwrap()
+            .into_log();
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("test log message"))
+                .level(log::Level::Info)
+                .target("my-test")
+                .build(),
+        );
+        logger.flush();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let expected_log_regex = format!(
+            r"^existing line\n\[{} INFO my-test\] test log message\n$",
+            timestamp_regex()
+        );
+        assert!(
+            predicates::str::is_match(expected_log_regex)
+                .unwrap()
+                .eval(&content),
+            "content: \"{content}\""
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_build_fd_logger_with_closed_fd() {
+        let config = LogDestinationConfig {
+            destination: LogDestination::Fd(999_999),
+            level: None,
+            most_severe: None,
+            filters: Vec::new(),
+        };
+        let error = build_logger(&config, LevelFilter::Info, "process_name".to_string())
+            .err()
+            .unwrap();
+        assert_eq!("Failed to open file descriptor 999999", error.to_string());
+    }
+
+    #[test]
+    fn test_build_tcp_logger() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = LogDestinationConfig {
+            destination: LogDestination::Tcp {
+                address: listener.local_addr().unwrap().to_string(),
+                format: LogFormat::Text,
+            },
+            level: None,
+            most_severe: None,
+            filters: Vec::new(),
+        };
+        let (_, logger) = build_logger(&config, LevelFilter::Info, "process_name".to_string())
+            .unwrap()
+            .into_log();
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("test log message"))
+                .level(log::Level::Info)
+                .target("my-test")
+                .build(),
+        );
+        logger.flush();
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut line = String::new();
+        std::io::BufRead::read_line(&mut std::io::BufReader::new(stream), &mut line).unwrap();
+        let expected_log_regex = format!(
+            r"^\[{} INFO my-test\] test log message\n$",
+            timestamp_regex()
+        );
+        assert!(
+            predicates::str::is_match(expected_log_regex)
+                .unwrap()
+                .eval(&line),
+            "line: \"{line}\""
+        );
+    }
+
+    #[test]
+    fn test_build_udp_logger() {
+        let receiver = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+        let config = LogDestinationConfig {
+            destination: LogDestination::Udp {
+                address: receiver.local_addr().unwrap().to_string(),
+                format: LogFormat::Json,
+            },
+            level: None,
+            most_severe: None,
+            filters: Vec::new(),
+        };
+        let (_, logger) = build_logger(&config, LevelFilter::Info, "process_name".to_string())
+            .unwrap()
+            .into_log();
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("test log message"))
+                .level(log::Level::Info)
+                .target("my-test")
+                .build(),
+        );
+
+        let mut buf = [0; 1024];
+        let len = receiver.recv(&mut buf).unwrap();
+        let line: serde_json::Value = serde_json::from_slice(&buf[..len]).unwrap();
+        assert_eq!("test log message", line["message"]);
+        assert_eq!("INFO", line["level"]);
+    }
+
     const fn timestamp_regex() -> &'static str {
         r"(\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)"
     }
@@ -283,10 +909,74 @@ mod tests {
         default_level: LevelFilter,
     ) {
         let config = LoggingConfig::disabled();
-        let built = build_main_logger(config, default_level, None, "process_name");
+        let built = build_main_logger(config, default_level, None, "process_name").unwrap();
         assert!(built.is_none());
     }
 
+    #[test]
+    fn test_build_main_logger_locked_file_fails() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("logfile");
+        let destination = LogDestinationConfig {
+            destination: LogDestination::File {
+                path: path.clone(),
+                options: crate::FileOptions {
+                    lock: crate::FileLock::Fail,
+                    ..crate::FileOptions::default()
+                },
+            },
+            level: None,
+            most_severe: None,
+            filters: Vec::new(),
+        };
+        let config = LoggingConfig::new(vec![destination.clone()]);
+        let _other = build_main_logger(config.clone(), LevelFilter::Info, None, "process_name")
+            .unwrap()
+            .unwrap()
+            .into_log();
+        let error = build_main_logger(config, LevelFilter::Info, None, "process_name").unwrap_err();
+        assert_eq!(
+            format!("Failed to open log destination `{destination}`"),
+            error.to_string()
+        );
+        assert_eq!(
+            format!("Log file `{}` is locked by another process", path.display()),
+            error.root_cause().to_string()
+        );
+    }
+
+    #[test]
+    fn test_build_main_logger_skips_destinations_that_cant_be_opened() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let file = tempdir.path().join("logfile");
+        let config = LoggingConfig::new(vec![
+            LogDestinationConfig::new(
+                LogDestination::file(tempdir.path().join("missing_dir").join("logfile")),
+                None,
+            ),
+            LogDestinationConfig::new(LogDestination::file(file.clone()), None),
+        ]);
+        let (_, logger) = build_main_logger(config, LevelFilter::Info, None, "process_name")
+            .unwrap()
+            .unwrap()
+            .into_log();
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("test log message"))
+                .level(log::Level::Info)
+                .target("my-test")
+                .build(),
+        );
+        logger.flush();
+
+        assert!(
+            std::fs::read_to_string(&file)
+                .unwrap()
+                .ends_with("test log message\n")
+        );
+        assert!(!tempdir.path().join("missing_dir").exists());
+    }
+
     #[rstest]
     fn test_build_main_logger_stderr_and_file(
         #[values(
@@ -304,13 +994,18 @@ mod tests {
             LogDestinationConfig {
                 destination: LogDestination::Stderr,
                 level: None,
+                most_severe: None,
+                filters: Vec::new(),
             },
             LogDestinationConfig {
-                destination: LogDestination::File(file.clone()),
+                destination: LogDestination::file(file.clone()),
                 level: None,
+                most_severe: None,
+                filters: Vec::new(),
             },
         ]);
         let (actual_level, logger) = build_main_logger(config, default_level, None, "process_name")
+            .unwrap()
             .unwrap()
             .into_log();
         assert_eq!(actual_level, default_level);
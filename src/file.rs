@@ -0,0 +1,660 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context as _, Result, bail};
+use fs4::fs_std::FileExt as _;
+
+use super::config::{FileLock, FileOptions, FsyncPolicy, LogFormat};
+use super::utils::{ignore_log_error, lock};
+
+/// How often [FileLogger] checks the free disk space and the size of the log file
+const SPACE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A [log::Log] implementation that writes each (already formatted) record as a line to a file.
+///
+/// Unlike fern's own file output, it syncs the file to the disk as configured by the [FsyncPolicy],
+/// and drops records below WARN while the log file exceeds its size limits.
+pub struct FileLogger {
+    state: Arc<Mutex<FileState>>,
+    path: PathBuf,
+    format: LogFormat,
+    fsync: FsyncPolicy,
+    min_free_space: Option<u64>,
+    max_size: Option<u64>,
+}
+
+struct FileState {
+    file: File,
+    /// Whether anything was written since the last sync
+    dirty: bool,
+    /// The size of the log file and the free disk space, as of the last check plus what we wrote since then
+    size: u64,
+    free_space: Option<u64>,
+    next_space_check: Instant,
+    /// Whether we're dropping records below WARN because a size limit is exceeded
+    limited: bool,
+}
+
+impl FileState {
+    fn sync(&mut self) -> std::io::Result<()> {
+        if self.dirty {
+            self.file.sync_data()?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        self.file.write_all(line.as_bytes())?;
+        let len = line.len() as u64;
+        self.dirty = true;
+        self.size += len;
+        self.free_space = self
+            .free_space
+            .map(|free_space| free_space.saturating_sub(len));
+        Ok(())
+    }
+}
+
+impl FileLogger {
+    /// Create the logger for a file opened with [open_log_file]. The path is used for the free disk space and in warnings.
+    pub fn new(file: File, path: &Path, options: &FileOptions) -> Self {
+        let state = Arc::new(Mutex::new(FileState {
+            file,
+            dirty: false,
+            size: 0,
+            free_space: None,
+            next_space_check: Instant::now(),
+            limited: false,
+        }));
+        if let FsyncPolicy::Periodic(interval) = options.fsync {
+            let state = Arc::downgrade(&state);
+            std::thread::spawn(move || {
+                loop {
+                    std::thread::sleep(interval);
+                    // Stop once the logger is dropped
+                    let Some(state) = state.upgrade() else {
+                        break;
+                    };
+                    ignore_log_error(lock(&state).sync());
+                }
+            });
+        }
+        Self {
+            state,
+            path: path.to_path_buf(),
+            format: options.format,
+            fsync: options.fsync,
+            min_free_space: options.min_free_space,
+            max_size: options.max_size,
+        }
+    }
+
+    /// Check whether writing `len` more bytes stays within the size limits, or return the exceeded limit.
+    fn exceeded_limit(&self, state: &mut FileState, len: u64) -> Option<String> {
+        if self.min_free_space.is_none() && self.max_size.is_none() {
+            return None;
+        }
+        if Instant::now() >= state.next_space_check {
+            // Re-read them, so we notice when the file was truncated or space was freed
+            if let Ok(metadata) = state.file.metadata() {
+                state.size = metadata.len();
+            }
+            if self.min_free_space.is_some() {
+                state.free_space = fs4::available_space(&self.path).ok();
+            }
+            state.next_space_check = Instant::now() + SPACE_CHECK_INTERVAL;
+        }
+        if let Some(max_size) = self.max_size
+            && state.size + len > max_size
+        {
+            return Some(format!("exceeds the maximum size of {max_size} bytes"));
+        }
+        if let Some(min_free_space) = self.min_free_space
+            && let Some(free_space) = state.free_space
+            && free_space.saturating_sub(len) < min_free_space
+        {
+            return Some(format!(
+                "has less than {min_free_space} bytes of free disk space"
+            ));
+        }
+        None
+    }
+
+    /// A line in the same format as the log records, for messages about the log file itself.
+    fn own_line(&self, level: log::Level, message: &str) -> String {
+        let timestamp = humantime::format_rfc3339_seconds(std::time::SystemTime::now());
+        let message = format!("Log file `{}` {message}", self.path.display());
+        match self.format {
+            LogFormat::Text => format!("[{timestamp} {level} {}] {message}\n", module_path!()),
+            LogFormat::Json => {
+                let line = serde_json::json!({
+                    "timestamp": timestamp.to_string(),
+                    "level": level.as_str(),
+                    "target": module_path!(),
+                    "message": message,
+                });
+                format!("{line}\n")
+            }
+        }
+    }
+}
+
+impl log::Log for FileLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let line = format!("{}\n", record.args());
+        let mut state = lock(&self.state);
+        match (
+            self.exceeded_limit(&mut state, line.len() as u64),
+            state.limited,
+        ) {
+            (Some(limit), false) => {
+                state.limited = true;
+                let warning = self.own_line(
+                    log::Level::Warn,
+                    &format!("{limit}, dropping log records below WARN until space is freed"),
+                );
+                ignore_log_error(state.write_line(&warning));
+            }
+            (None, true) => {
+                state.limited = false;
+                let message = self.own_line(
+                    log::Level::Info,
+                    "has enough space again, resuming logging of all records",
+                );
+                ignore_log_error(state.write_line(&message));
+            }
+            _ => {}
+        }
+        if state.limited && record.level() > log::Level::Warn {
+            return;
+        }
+        if state.write_line(&line).is_err() {
+            return;
+        }
+        let sync = match self.fsync {
+            FsyncPolicy::Always => true,
+            FsyncPolicy::Errors => record.level() == log::Level::Error,
+            FsyncPolicy::Never | FsyncPolicy::Periodic(_) => false,
+        };
+        if sync {
+            ignore_log_error(state.sync());
+        }
+    }
+
+    fn flush(&self) {
+        ignore_log_error(lock(&self.state).sync());
+    }
+}
+
+/// The highest suffix tried with [FileLock::Suffix] before giving up
+const MAX_SUFFIX: u32 = 100;
+
+/// The error if a log file with [FileLock::Fail] is locked by another process.
+///
+/// Unlike other errors when opening log destinations, this makes initializing logging fail, since the application asked for it.
+#[derive(Debug)]
+pub struct FileLockedError(PathBuf);
+
+impl std::fmt::Display for FileLockedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Log file `{}` is locked by another process",
+            self.0.display()
+        )
+    }
+}
+
+impl std::error::Error for FileLockedError {}
+
+/// Open a log file for writing, as configured by the [FileOptions].
+pub fn open_log_file(path: &Path, options: &FileOptions) -> Result<File> {
+    if options.create_dirs
+        && let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory `{}`", parent.display()))?;
+    }
+
+    let file = match options.lock {
+        FileLock::Disabled => open(path, options)?,
+        FileLock::Fail => {
+            let file = open(path, options)?;
+            if !try_lock(&file, path)? {
+                bail!(FileLockedError(path.to_path_buf()));
+            }
+            file
+        }
+        FileLock::Wait => {
+            let file = open(path, options)?;
+            file.lock_exclusive()
+                .with_context(|| format!("Failed to lock log file `{}`", path.display()))?;
+            file
+        }
+        FileLock::Suffix => open_first_unlocked(path, options)?,
+    };
+    if options.truncate {
+        // Only truncate once we hold the lock, so we don't truncate a log file another process is writing to
+        file.set_len(0)
+            .with_context(|| format!("Failed to truncate log file `{}`", path.display()))?;
+    }
+    Ok(file)
+}
+
+fn open(path: &Path, options: &FileOptions) -> Result<File> {
+    let mut open_options = OpenOptions::new();
+    open_options.create(true);
+    if options.truncate {
+        open_options.write(true);
+    } else {
+        open_options.append(true);
+    }
+    #[cfg(unix)]
+    if let Some(mode) = options.mode {
+        use std::os::unix::fs::OpenOptionsExt as _;
+        open_options.mode(mode);
+    }
+    open_options
+        .open(path)
+        .with_context(|| format!("Failed to open log file `{}`", path.display()))
+}
+
+fn try_lock(file: &File, path: &Path) -> Result<bool> {
+    file.try_lock_exclusive()
+        .with_context(|| format!("Failed to lock log file `{}`", path.display()))
+}
+
+fn open_first_unlocked(path: &Path, options: &FileOptions) -> Result<File> {
+    let candidates =
+        std::iter::once(path.to_path_buf()).chain((1..=MAX_SUFFIX).map(|n| suffixed_path(path, n)));
+    for candidate in candidates {
+        let file = open(&candidate, options)?;
+        if try_lock(&file, &candidate)? {
+            return Ok(file);
+        }
+    }
+    bail!(
+        "Log file `{}` and its suffixed alternatives up to `{}` are locked by other processes",
+        path.display(),
+        suffixed_path(path, MAX_SUFFIX).display()
+    )
+}
+
+/// Add a numeric suffix in front of the extension, e.g. `app.log` becomes `app.1.log`.
+fn suffixed_path(path: &Path, suffix: u32) -> PathBuf {
+    let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(format!(".{suffix}"));
+    if let Some(extension) = path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Log as _;
+    use rstest::rstest;
+
+    #[test]
+    fn appends_by_default() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("logfile");
+        std::fs::write(&path, "existing line\n").unwrap();
+        let mut file = open_log_file(&path, &FileOptions::default()).unwrap();
+        writeln!(file, "new line").unwrap();
+        assert_eq!(
+            "existing line\nnew line\n",
+            std::fs::read_to_string(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn truncates() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("logfile");
+        std::fs::write(&path, "existing line\n").unwrap();
+        let options = FileOptions {
+            truncate: true,
+            ..FileOptions::default()
+        };
+        let mut file = open_log_file(&path, &options).unwrap();
+        writeln!(file, "new line").unwrap();
+        assert_eq!("new line\n", std::fs::read_to_string(&path).unwrap());
+    }
+
+    #[test]
+    fn fails_without_parent_dir() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("missing").join("logfile");
+        let error = open_log_file(&path, &FileOptions::default()).unwrap_err();
+        assert_eq!(
+            format!("Failed to open log file `{}`", path.display()),
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn creates_parent_dirs() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("missing").join("dirs").join("logfile");
+        let options = FileOptions {
+            create_dirs: true,
+            ..FileOptions::default()
+        };
+        let mut file = open_log_file(&path, &options).unwrap();
+        writeln!(file, "new line").unwrap();
+        assert_eq!("new line\n", std::fs::read_to_string(&path).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sets_mode_on_creation() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("logfile");
+        let options = FileOptions {
+            mode: Some(0o600),
+            ..FileOptions::default()
+        };
+        open_log_file(&path, &options).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(0o600, mode & 0o777);
+
+        // Existing files keep their permissions
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+        open_log_file(&path, &options).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(0o640, mode & 0o777);
+    }
+
+    fn locked_options(lock: FileLock) -> FileOptions {
+        FileOptions {
+            lock,
+            ..FileOptions::default()
+        }
+    }
+
+    #[rstest]
+    #[case("/var/log/app.log", "/var/log/app.3.log")]
+    #[case("/var/log/app", "/var/log/app.3")]
+    #[case("/var/log/app.tar.gz", "/var/log/app.tar.3.gz")]
+    #[case("app.log", "app.3.log")]
+    fn suffixes_path(#[case] path: &str, #[case] expected: &str) {
+        assert_eq!(Path::new(expected), suffixed_path(Path::new(path), 3));
+    }
+
+    #[rstest]
+    fn unlocked_file_can_be_opened(
+        #[values(FileLock::Disabled, FileLock::Fail, FileLock::Wait, FileLock::Suffix)]
+        lock: FileLock,
+    ) {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("app.log");
+        let mut file = open_log_file(&path, &locked_options(lock)).unwrap();
+        writeln!(file, "new line").unwrap();
+        assert_eq!("new line\n", std::fs::read_to_string(&path).unwrap());
+    }
+
+    #[test]
+    fn locked_file_fails() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("app.log");
+        let _other = open_log_file(&path, &locked_options(FileLock::Fail)).unwrap();
+        let error = open_log_file(&path, &locked_options(FileLock::Fail)).unwrap_err();
+        assert_eq!(
+            format!("Log file `{}` is locked by another process", path.display()),
+            error.to_string()
+        );
+        // Without locking, the file can still be opened
+        open_log_file(&path, &FileOptions::default()).unwrap();
+    }
+
+    #[test]
+    fn locked_file_isnt_truncated() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("app.log");
+        let mut other = open_log_file(&path, &locked_options(FileLock::Fail)).unwrap();
+        writeln!(other, "other process").unwrap();
+        let options = FileOptions {
+            truncate: true,
+            lock: FileLock::Fail,
+            ..FileOptions::default()
+        };
+        open_log_file(&path, &options).unwrap_err();
+        assert_eq!("other process\n", std::fs::read_to_string(&path).unwrap());
+    }
+
+    #[test]
+    fn locked_file_falls_back_to_suffix() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("app.log");
+        let _first = open_log_file(&path, &locked_options(FileLock::Suffix)).unwrap();
+        let _second = open_log_file(&path, &locked_options(FileLock::Suffix)).unwrap();
+        let mut third = open_log_file(&path, &locked_options(FileLock::Suffix)).unwrap();
+        writeln!(third, "third process").unwrap();
+        assert!(tempdir.path().join("app.1.log").exists());
+        assert_eq!(
+            "third process\n",
+            std::fs::read_to_string(tempdir.path().join("app.2.log")).unwrap()
+        );
+    }
+
+    #[test]
+    fn locked_file_waits() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("app.log");
+        let mut first = open_log_file(&path, &locked_options(FileLock::Wait)).unwrap();
+        let waiting_thread = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                let mut second = open_log_file(&path, &locked_options(FileLock::Wait)).unwrap();
+                writeln!(second, "second process").unwrap();
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        writeln!(first, "first process").unwrap();
+        drop(first);
+        waiting_thread.join().unwrap();
+        assert_eq!(
+            "first process\nsecond process\n",
+            std::fs::read_to_string(&path).unwrap()
+        );
+    }
+
+    fn log_line(logger: &FileLogger, level: log::Level, message: &str) {
+        logger.log(
+            &log::Record::builder()
+                .level(level)
+                .args(format_args!("{message}"))
+                .build(),
+        );
+    }
+
+    fn open_logger(path: &Path, options: FileOptions) -> FileLogger {
+        FileLogger::new(open_log_file(path, &options).unwrap(), path, &options)
+    }
+
+    fn is_dirty(logger: &FileLogger) -> bool {
+        lock(&logger.state).dirty
+    }
+
+    #[rstest]
+    #[case(FsyncPolicy::Always, log::Level::Info, false)]
+    #[case(FsyncPolicy::Errors, log::Level::Info, true)]
+    #[case(FsyncPolicy::Errors, log::Level::Error, false)]
+    #[case(
+        FsyncPolicy::Periodic(Duration::from_secs(3600)),
+        log::Level::Error,
+        true
+    )]
+    fn syncs_by_policy(
+        #[case] fsync: FsyncPolicy,
+        #[case] level: log::Level,
+        #[case] expected_dirty: bool,
+    ) {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("app.log");
+        let logger = open_logger(
+            &path,
+            FileOptions {
+                fsync,
+                ..FileOptions::default()
+            },
+        );
+        log_line(&logger, level, "message");
+        assert_eq!(expected_dirty, is_dirty(&logger));
+        assert_eq!("message\n", std::fs::read_to_string(&path).unwrap());
+
+        logger.flush();
+        assert!(!is_dirty(&logger));
+    }
+
+    #[test]
+    fn syncs_periodically() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("app.log");
+        let logger = open_logger(
+            &path,
+            FileOptions {
+                fsync: FsyncPolicy::Periodic(Duration::from_millis(10)),
+                ..FileOptions::default()
+            },
+        );
+        log_line(&logger, log::Level::Info, "message");
+        let start = Instant::now();
+        while is_dirty(&logger) {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "File wasn't synced"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// The log file without timestamps
+    fn read_log(path: &Path) -> String {
+        let timestamp = regex::Regex::new(r"(?m)^\[\S+ ").unwrap();
+        timestamp
+            .replace_all(&std::fs::read_to_string(path).unwrap(), "[")
+            .into_owned()
+    }
+
+    #[test]
+    fn drops_records_below_warn_above_max_size() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("app.log");
+        let logger = open_logger(
+            &path,
+            FileOptions {
+                max_size: Some(20),
+                ..FileOptions::default()
+            },
+        );
+        log_line(&logger, log::Level::Info, "first info");
+        log_line(&logger, log::Level::Info, "second info");
+        log_line(&logger, log::Level::Debug, "dropped debug");
+        log_line(&logger, log::Level::Warn, "kept warn");
+        log_line(&logger, log::Level::Error, "kept error");
+        assert_eq!(
+            format!(
+                "first info\n[WARN clap_logflag::file] Log file `{}` exceeds the maximum size of 20 bytes, dropping log records below WARN until space is freed\nkept warn\nkept error\n",
+                path.display()
+            ),
+            read_log(&path)
+        );
+    }
+
+    #[test]
+    fn resumes_when_space_is_freed() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("app.log");
+        let logger = open_logger(
+            &path,
+            FileOptions {
+                max_size: Some(20),
+                ..FileOptions::default()
+            },
+        );
+        log_line(&logger, log::Level::Info, "first info");
+        log_line(&logger, log::Level::Info, "dropped info");
+        log_line(&logger, log::Level::Info, "dropped again");
+
+        // Someone truncates the log file
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(0)
+            .unwrap();
+        lock(&logger.state).next_space_check = Instant::now();
+        log_line(&logger, log::Level::Info, "resumed");
+        assert_eq!(
+            format!(
+                "[INFO clap_logflag::file] Log file `{}` has enough space again, resuming logging of all records\nresumed\n",
+                path.display()
+            ),
+            read_log(&path).trim_start_matches('\0')
+        );
+    }
+
+    #[test]
+    fn drops_records_below_warn_without_free_space() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("app.log");
+        let logger = open_logger(
+            &path,
+            FileOptions {
+                min_free_space: Some(u64::MAX),
+                ..FileOptions::default()
+            },
+        );
+        log_line(&logger, log::Level::Info, "dropped info");
+        log_line(&logger, log::Level::Warn, "kept warn");
+        assert_eq!(
+            format!(
+                "[WARN clap_logflag::file] Log file `{}` has less than {} bytes of free disk space, dropping log records below WARN until space is freed\nkept warn\n",
+                path.display(),
+                u64::MAX
+            ),
+            read_log(&path)
+        );
+    }
+
+    #[test]
+    fn writes_json_warning() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("app.log");
+        let logger = open_logger(
+            &path,
+            FileOptions {
+                format: LogFormat::Json,
+                max_size: Some(0),
+                ..FileOptions::default()
+            },
+        );
+        log_line(&logger, log::Level::Info, "dropped info");
+        let content = std::fs::read_to_string(&path).unwrap();
+        let warning: serde_json::Value = serde_json::from_str(content.trim_end()).unwrap();
+        assert_eq!("WARN", warning["level"]);
+        assert_eq!("clap_logflag::file", warning["target"]);
+        assert_eq!(
+            format!(
+                "Log file `{}` exceeds the maximum size of 0 bytes, dropping log records below WARN until space is freed",
+                path.display()
+            ),
+            warning["message"]
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use anyhow::{Context as _, Result};
+use regex::Regex;
+
+use super::config::{LogFilter, LogFilterField, LogFilterKind, LogFilterPattern};
+
+/// A [log::Log] implementation that only forwards records matching the filters of a destination, see [LogFilter].
+///
+/// Records are matched before they're formatted, so the message doesn't contain the timestamp or level.
+pub struct FilteringLogger {
+    include: Vec<CompiledFilter>,
+    exclude: Vec<CompiledFilter>,
+    inner: Box<dyn log::Log>,
+}
+
+impl FilteringLogger {
+    pub fn new(filters: &[LogFilter], inner: Box<dyn log::Log>) -> Result<Self> {
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for filter in filters {
+            let compiled = CompiledFilter::new(filter)?;
+            match filter.kind {
+                LogFilterKind::Include => include.push(compiled),
+                LogFilterKind::Exclude => exclude.push(compiled),
+            }
+        }
+        Ok(Self {
+            include,
+            exclude,
+            inner,
+        })
+    }
+
+    fn matches(&self, record: &log::Record) -> bool {
+        // Only format the message once, and only if a filter needs it
+        let mut message = None;
+        let mut field = |field: LogFilterField| -> String {
+            match field {
+                LogFilterField::Message => message
+                    .get_or_insert_with(|| record.args().to_string())
+                    .clone(),
+                LogFilterField::Target => record.target().to_string(),
+            }
+        };
+        let mut matches_any = |filters: &[CompiledFilter]| {
+            filters
+                .iter()
+                .any(|filter| filter.pattern.is_match(&field(filter.field)))
+        };
+        (self.include.is_empty() || matches_any(&self.include)) && !matches_any(&self.exclude)
+    }
+}
+
+impl log::Log for FilteringLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.matches(record) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+struct CompiledFilter {
+    field: LogFilterField,
+    pattern: CompiledPattern,
+}
+
+impl CompiledFilter {
+    fn new(filter: &LogFilter) -> Result<Self> {
+        let pattern = match &filter.pattern {
+            LogFilterPattern::Substring(substring) => CompiledPattern::Substring(substring.clone()),
+            LogFilterPattern::Regex(regex) => CompiledPattern::Regex(
+                Regex::new(regex)
+                    .with_context(|| format!("Invalid regular expression `{regex}`"))?,
+            ),
+        };
+        Ok(Self {
+            field: filter.field,
+            pattern,
+        })
+    }
+}
+
+enum CompiledPattern {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl CompiledPattern {
+    fn is_match(&self, value: &str) -> bool {
+        match self {
+            CompiledPattern::Substring(substring) => value.contains(substring.as_str()),
+            CompiledPattern::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Collector(Arc<Mutex<Vec<String>>>);
+
+    impl log::Log for Collector {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn filter(kind: LogFilterKind, field: LogFilterField, pattern: LogFilterPattern) -> LogFilter {
+        LogFilter {
+            kind,
+            field,
+            pattern,
+        }
+    }
+
+    fn logged(filters: &[LogFilter]) -> Vec<String> {
+        let collector = Collector::default();
+        let logger = FilteringLogger::new(filters, Box::new(collector.clone())).unwrap();
+        for (target, message) in [
+            ("app::server", "GET /healthcheck 200"),
+            ("app::server", "GET /users 200"),
+            ("hyper::proto", "parsed 3 headers"),
+        ] {
+            log::Log::log(
+                &logger,
+                &log::Record::builder()
+                    .target(target)
+                    .args(format_args!("{message}"))
+                    .build(),
+            );
+        }
+        collector.0.lock().unwrap().clone()
+    }
+
+    #[rstest]
+    #[case(vec![], &["GET /healthcheck 200", "GET /users 200", "parsed 3 headers"])]
+    #[case(
+        vec![filter(LogFilterKind::Exclude, LogFilterField::Message, LogFilterPattern::Substring("healthcheck".to_string()))],
+        &["GET /users 200", "parsed 3 headers"]
+    )]
+    #[case(
+        vec![filter(LogFilterKind::Include, LogFilterField::Message, LogFilterPattern::Regex("^GET ".to_string()))],
+        &["GET /healthcheck 200", "GET /users 200"]
+    )]
+    #[case(
+        vec![filter(LogFilterKind::Exclude, LogFilterField::Target, LogFilterPattern::Substring("hyper".to_string()))],
+        &["GET /healthcheck 200", "GET /users 200"]
+    )]
+    #[case(
+        vec![
+            filter(LogFilterKind::Include, LogFilterField::Target, LogFilterPattern::Regex("^app::".to_string())),
+            filter(LogFilterKind::Exclude, LogFilterField::Message, LogFilterPattern::Regex("/health".to_string())),
+        ],
+        &["GET /users 200"]
+    )]
+    #[case(
+        vec![
+            filter(LogFilterKind::Include, LogFilterField::Message, LogFilterPattern::Substring("users".to_string())),
+            filter(LogFilterKind::Include, LogFilterField::Target, LogFilterPattern::Substring("hyper".to_string())),
+        ],
+        &["GET /users 200", "parsed 3 headers"]
+    )]
+    fn filters_records(#[case] filters: Vec<LogFilter>, #[case] expected: &[&str]) {
+        assert_eq!(expected, logged(&filters));
+    }
+
+    #[test]
+    fn invalid_regex() {
+        let error = FilteringLogger::new(
+            &[filter(
+                LogFilterKind::Exclude,
+                LogFilterField::Message,
+                LogFilterPattern::Regex("(".to_string()),
+            )],
+            Box::new(Collector::default()),
+        )
+        .err()
+        .unwrap();
+        assert_eq!("Invalid regular expression `(`", error.to_string());
+    }
+}
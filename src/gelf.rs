@@ -0,0 +1,404 @@
+use std::hash::{BuildHasher as _, RandomState};
+use std::io::Write as _;
+use std::net::UdpSocket;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+use super::config::GelfTransport;
+use super::fern::JsonFieldCollector;
+use super::line_logger::{BackgroundLineWriter, LineWriter as _};
+use super::network::{ReconnectingLineWriter, connect_udp};
+use super::utils::{collect_key_values, ignore_log_error, lock};
+
+/// Maximum size of a UDP datagram. Larger messages are split into chunks.
+/// This fits into the MTU of most networks, so datagrams don't get fragmented.
+const MAX_DATAGRAM_SIZE: usize = 1420;
+/// Magic bytes, message id, sequence number and sequence count
+const CHUNK_HEADER_SIZE: usize = 12;
+const CHUNK_MAGIC: [u8; 2] = [0x1e, 0x0f];
+/// GELF inputs drop messages with more chunks than this
+const MAX_CHUNKS: usize = 128;
+
+/// A [log::Log] implementation that sends records as GELF messages, e.g. to Graylog.
+///
+/// Records must not be formatted before, since the GELF message is built from the original log message.
+pub struct GelfLogger {
+    host: String,
+    writer: Mutex<GelfWriter>,
+}
+
+enum GelfWriter {
+    Udp {
+        socket: UdpSocket,
+        compress: bool,
+        message_ids: MessageIds,
+    },
+    Tcp(BackgroundLineWriter),
+}
+
+impl GelfLogger {
+    pub fn new(address: &str, transport: GelfTransport) -> Result<Self> {
+        let writer = match transport {
+            GelfTransport::Udp { compress } => GelfWriter::Udp {
+                socket: connect_udp(address)?,
+                compress,
+                message_ids: MessageIds::new(),
+            },
+            GelfTransport::Tcp => GelfWriter::Tcp(BackgroundLineWriter::new(
+                ReconnectingLineWriter::tcp(address).with_delimiter(b'\0'),
+            )),
+        };
+        Ok(Self {
+            host: hostname::get()
+                .map(|host| host.to_string_lossy().into_owned())
+                .unwrap_or_else(|_| "localhost".to_string()),
+            writer: Mutex::new(writer),
+        })
+    }
+}
+
+impl log::Log for GelfLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let message = gelf_message(&self.host, record, SystemTime::now()).to_string();
+        // The TCP writer reconnects by itself on a background thread
+        ignore_log_error(match &mut *lock(&self.writer) {
+            GelfWriter::Udp {
+                socket,
+                compress,
+                message_ids,
+            } => send_udp(socket, &message, *compress, message_ids),
+            GelfWriter::Tcp(writer) => writer.write_line(&message),
+        });
+    }
+
+    fn flush(&self) {
+        if let GelfWriter::Tcp(writer) = &mut *lock(&self.writer) {
+            ignore_log_error(writer.flush());
+        }
+    }
+}
+
+fn gelf_message(host: &str, record: &log::Record, timestamp: SystemTime) -> Value {
+    let full_message = record.args().to_string();
+    let short_message = full_message.lines().next().unwrap_or_default();
+
+    let mut message = Map::new();
+    message.insert("version".to_string(), "1.1".into());
+    message.insert("host".to_string(), host.into());
+    message.insert("short_message".to_string(), short_message.into());
+    if short_message != full_message {
+        message.insert("full_message".to_string(), full_message.into());
+    }
+    let timestamp = timestamp
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    // Graylog only keeps millisecond precision
+    message.insert(
+        "timestamp".to_string(),
+        (timestamp.as_millis() as f64 / 1000.0).into(),
+    );
+    message.insert("level".to_string(), syslog_severity(record.level()).into());
+    message.insert("_target".to_string(), record.target().into());
+    if let Some(file) = record.file() {
+        message.insert("_file".to_string(), file.into());
+    }
+    if let Some(line) = record.line() {
+        message.insert("_line".to_string(), line.into());
+    }
+
+    let JsonFieldCollector(fields) = collect_key_values(record);
+    for (key, value) in fields {
+        message.insert(field_name(&key), value);
+    }
+    Value::Object(message)
+}
+
+/// GELF uses the syslog severities as levels
+fn syslog_severity(level: log::Level) -> u8 {
+    match level {
+        log::Level::Error => 3,
+        log::Level::Warn => 4,
+        log::Level::Info => 6,
+        log::Level::Debug | log::Level::Trace => 7,
+    }
+}
+
+/// Turn a key of a log record into the name of an additional GELF field.
+///
+/// Additional field names must start with an underscore and may only contain letters, numbers, underscores,
+/// dashes and dots. `_id` is reserved, so a key `id` becomes `__id`.
+fn field_name(key: &str) -> String {
+    let key: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if key == "id" {
+        "__id".to_string()
+    } else {
+        format!("_{key}")
+    }
+}
+
+fn send_udp(
+    socket: &UdpSocket,
+    message: &str,
+    compress: bool,
+    message_ids: &MessageIds,
+) -> std::io::Result<()> {
+    let payload = if compress {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(message.as_bytes())?;
+        encoder.finish()?
+    } else {
+        message.as_bytes().to_vec()
+    };
+    for datagram in datagrams(&payload, message_ids.next())? {
+        socket.send(&datagram)?;
+    }
+    Ok(())
+}
+
+/// Split the payload into GELF chunks if it doesn't fit into a single datagram.
+fn datagrams(payload: &[u8], message_id: u64) -> std::io::Result<Vec<Vec<u8>>> {
+    if payload.len() <= MAX_DATAGRAM_SIZE {
+        return Ok(vec![payload.to_vec()]);
+    }
+    let chunks: Vec<&[u8]> = payload
+        .chunks(MAX_DATAGRAM_SIZE - CHUNK_HEADER_SIZE)
+        .collect();
+    if chunks.len() > MAX_CHUNKS {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "GELF message is too large to be sent over UDP",
+        ));
+    }
+    let count = chunks.len() as u8;
+    Ok(chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut datagram = Vec::with_capacity(CHUNK_HEADER_SIZE + chunk.len());
+            datagram.extend_from_slice(&CHUNK_MAGIC);
+            datagram.extend_from_slice(&message_id.to_be_bytes());
+            datagram.push(index as u8);
+            datagram.push(count);
+            datagram.extend_from_slice(chunk);
+            datagram
+        })
+        .collect())
+}
+
+/// Generates the ids that identify the chunks belonging to a message.
+/// They only need to be unique among the messages a GELF input is currently reassembling.
+struct MessageIds {
+    random_state: RandomState,
+    counter: AtomicU64,
+}
+
+impl MessageIds {
+    fn new() -> Self {
+        Self {
+            random_state: RandomState::new(),
+            counter: AtomicU64::new(0),
+        }
+    }
+
+    fn next(&self) -> u64 {
+        self.random_state
+            .hash_one(self.counter.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Log as _;
+    use std::io::Read as _;
+    use std::time::Duration;
+
+    fn receiver() -> UdpSocket {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        receiver
+    }
+
+    fn log(logger: &GelfLogger, message: &str) {
+        let key_values: [(&str, log::kv::Value); 3] = [
+            ("user name", "alice".into()),
+            ("attempt", 3.into()),
+            ("id", 42.into()),
+        ];
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("{message}"))
+                .level(log::Level::Warn)
+                .target("my-test")
+                .file(Some("src/main.rs"))
+                .line(Some(12))
+                .key_values(&key_values)
+                .build(),
+        );
+    }
+
+    fn recv(receiver: &UdpSocket) -> Vec<u8> {
+        let mut buf = vec![0; 65536];
+        let len = receiver.recv(&mut buf).unwrap();
+        buf.truncate(len);
+        buf
+    }
+
+    #[test]
+    fn message_fields() {
+        let record_args = format_args!("first line\nsecond line");
+        let key_values = [("user name", "alice"), ("id", "42")];
+        let record = log::Record::builder()
+            .args(record_args)
+            .level(log::Level::Info)
+            .target("my-test")
+            .file(Some("src/main.rs"))
+            .line(Some(12))
+            .key_values(&key_values)
+            .build();
+        let timestamp = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        assert_eq!(
+            serde_json::json!({
+                "version": "1.1",
+                "host": "my-host",
+                "short_message": "first line",
+                "full_message": "first line\nsecond line",
+                "timestamp": 1_700_000_000.123,
+                "level": 6,
+                "_target": "my-test",
+                "_file": "src/main.rs",
+                "_line": 12,
+                "_user_name": "alice",
+                "__id": "42",
+            }),
+            gelf_message("my-host", &record, timestamp)
+        );
+    }
+
+    #[test]
+    fn levels() {
+        assert_eq!(3, syslog_severity(log::Level::Error));
+        assert_eq!(4, syslog_severity(log::Level::Warn));
+        assert_eq!(6, syslog_severity(log::Level::Info));
+        assert_eq!(7, syslog_severity(log::Level::Debug));
+        assert_eq!(7, syslog_severity(log::Level::Trace));
+    }
+
+    #[test]
+    fn udp() {
+        let receiver = receiver();
+        let logger = GelfLogger::new(
+            &receiver.local_addr().unwrap().to_string(),
+            GelfTransport::Udp { compress: false },
+        )
+        .unwrap();
+        log(&logger, "test log message");
+        let message: Value = serde_json::from_slice(&recv(&receiver)).unwrap();
+        assert_eq!("test log message", message["short_message"]);
+        assert_eq!(4, message["level"]);
+        assert_eq!(3, message["_attempt"]);
+        assert_eq!(42, message["__id"]);
+    }
+
+    #[test]
+    fn udp_compressed() {
+        let receiver = receiver();
+        let logger = GelfLogger::new(
+            &receiver.local_addr().unwrap().to_string(),
+            GelfTransport::Udp { compress: true },
+        )
+        .unwrap();
+        log(&logger, "test log message");
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(recv(&receiver).as_slice())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        let message: Value = serde_json::from_str(&decompressed).unwrap();
+        assert_eq!("test log message", message["short_message"]);
+    }
+
+    #[test]
+    fn udp_chunked() {
+        let receiver = receiver();
+        let logger = GelfLogger::new(
+            &receiver.local_addr().unwrap().to_string(),
+            GelfTransport::Udp { compress: false },
+        )
+        .unwrap();
+        let long_message = "x".repeat(5000);
+        log(&logger, &long_message);
+
+        let mut payload = Vec::new();
+        let mut message_id = None;
+        let mut index = 0;
+        loop {
+            let datagram = recv(&receiver);
+            assert!(datagram.len() <= MAX_DATAGRAM_SIZE);
+            assert_eq!(CHUNK_MAGIC, datagram[..2]);
+            let id = &datagram[2..10];
+            assert_eq!(*message_id.get_or_insert(id.to_vec()), id);
+            assert_eq!(index, datagram[10]);
+            let count = datagram[11];
+            payload.extend_from_slice(&datagram[CHUNK_HEADER_SIZE..]);
+            index += 1;
+            if index == count {
+                break;
+            }
+        }
+        assert_eq!(4, index);
+        let message: Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(long_message, message["short_message"]);
+    }
+
+    #[test]
+    fn too_many_chunks() {
+        let payload = vec![0; MAX_CHUNKS * (MAX_DATAGRAM_SIZE - CHUNK_HEADER_SIZE) + 1];
+        assert!(datagrams(&payload, 0).is_err());
+        assert_eq!(MAX_CHUNKS, datagrams(&payload[1..], 0).unwrap().len());
+    }
+
+    #[test]
+    fn tcp() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let logger = GelfLogger::new(
+            &listener.local_addr().unwrap().to_string(),
+            GelfTransport::Tcp,
+        )
+        .unwrap();
+        log(&logger, "first message");
+        log(&logger, "second message");
+        drop(logger);
+
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).unwrap();
+        let messages: Vec<Value> = received
+            .split(|byte| *byte == 0)
+            .filter(|message| !message.is_empty())
+            .map(|message| serde_json::from_slice(message).unwrap())
+            .collect();
+        assert_eq!(2, messages.len());
+        assert_eq!("first message", messages[0]["short_message"]);
+        assert_eq!("second message", messages[1]["short_message"]);
+        assert!(received.ends_with(&[0]));
+    }
+}
@@ -46,8 +46,8 @@
 //! fn main() {
 //!     let args = CliArgs::parse();
 //!
-//!     // Initialize logging with the flags from clap
-//!     clap_logflag::init_logging!(
+//!     // Initialize logging with the flags from clap. Logs are flushed when `_logging` is dropped at the end of `main`.
+//!     let _logging = clap_logflag::init_logging!(
 //!         args.log
 //!             // If no `--log` arguments are present, disable logging.
 //!             // You can change this to define the default behavior,
@@ -67,6 +67,68 @@
 //! }
 //! ```
 //!
+//! # Logging panics
+//! By default, panics are only reported on stderr. If you want them to also show up in the configured log destinations,
+//! e.g. because your application only logs to syslog or a file, pass [InitOptions](crate::InitOptions) with a
+//! [PanicHook](crate::PanicHook) to [init_logging!](crate::init_logging). Panics will then be logged at ERROR level,
+//! optionally with a backtrace, before the previously installed panic hook runs.
+//!
+//! # Custom log destinations
+//! Applications can add their own log destinations by implementing the [LogSink](crate::LogSink) trait and registering it
+//! for a scheme with [register_sink](crate::register_sink) before parsing the command line arguments.
+//! Users can then log to it with `--log INFO:myqueue:topic`.
+//!
+//! To show log messages inside of your application, e.g. in a GUI or TUI, register a [CallbackSink](crate::CallbackSink)
+//! that forwards each log record to a closure or channel.
+//!
+//! # Testing
+//! To check the log output of your application in tests, log to an in-memory buffer with `--log memory:name`
+//! (or [LogDestination::Memory](crate::LogDestination::Memory)) and inspect the captured records through
+//! [MemoryBuffer](crate::MemoryBuffer), e.g. `MemoryBuffer::get("name").assert_logged(Level::Warn, "regex")`.
+//!
+//! # Tracing
+//! If your application uses [tracing](https://crates.io/crates/tracing) instead of [log](https://crates.io/crates/log),
+//! enable the `tracing` feature and use [tracing_layer!](https://docs.rs/clap-logflag/latest/clap_logflag/macro.tracing_layer.html) instead of [init_logging!](crate::init_logging).
+//! It builds a [tracing-subscriber](https://crates.io/crates/tracing-subscriber) layer from the same [LoggingConfig](crate::LoggingConfig),
+//! so the `--log` flag works unchanged. Span fields are rendered in front of the log message.
+//!
+//! ```rust,ignore
+//! use tracing_subscriber::layer::SubscriberExt as _;
+//! use tracing_subscriber::util::SubscriberInitExt as _;
+//!
+//! let layer = clap_logflag::tracing_layer!(
+//!     args.log.or_default(LoggingConfig::disabled()),
+//!     LevelFilter::Info,
+//! );
+//! // Flushes the log destinations at the end of `main`
+//! let _logging = layer.guard();
+//! tracing_subscriber::registry().with(layer).init();
+//! ```
+//!
+//! # OpenTelemetry
+//! Enable the `otlp` feature to export log records to an [OpenTelemetry](https://opentelemetry.io/) collector
+//! with `--log INFO:otlp:http://localhost:4318`. Records are exported in batches, with the process name as `service.name`.
+//!
+//! # Child processes
+//! If your application spawns other programs that also use clap-logflag, you can pass the logging configuration on to them.
+//! [LoggingConfig::active](crate::LoggingConfig::active) returns the configuration logging was initialized with.
+//! Add it to a [Command](std::process::Command) as `--log` arguments with [LoggingConfig::add_command_args](crate::LoggingConfig::add_command_args),
+//! or as an environment variable with [LoggingConfig::set_command_env](crate::LoggingConfig::set_command_env) that the child reads with
+//! [LoggingConfig::from_env](crate::LoggingConfig::from_env). Use [LoggingConfig::map_file_paths](crate::LoggingConfig::map_file_paths)
+//! to give each child its own log file, e.g. with a `{pid}` placeholder.
+//!
+//! ```rust,no_run
+//! use clap_logflag::LoggingConfig;
+//!
+//! let mut command = std::process::Command::new("worker");
+//! if let Some(config) = LoggingConfig::active() {
+//!     config
+//!         .map_file_paths(|path| path.with_extension("{pid}.log"))
+//!         .add_command_args(&mut command);
+//! }
+//! command.spawn().unwrap();
+//! ```
+//!
 //! # Command Line Syntax
 //! See [LogArgs](crate::clap::LogArgs) for a detailed explanation of the syntax for the `--log` argument.
 //!
@@ -98,9 +160,34 @@
 //!     
 //!     The argument can be combined with a level filter to only log messages of a certain level or higher to that destination.
 //!     
-//!     Format: destination | level_filter:destination
-//!     * level_filter = "ERROR" | "WARN" | "INFO" | "DEBUG" | "TRACE"
-//!     * destination = "stderr" | "syslog" | "file:path" | "none"
+//!     File paths can contain the placeholders `{pid}`, `{exe}`, `{date}`, `{hostname}` and `{user}`,
+//!     a leading `~` for the home directory, and environment variables like `$VAR`.
+//!     
+//!     A ring buffer keeps the last `capacity` log messages in memory and only writes them to its sink (stderr or a file)
+//!     when an error is logged, the application panics, or the process receives SIGUSR1. File options apply to a file sink, e.g. `ringbuffer:1000:file:/path;format=json`.
+//!     
+//!     A pipe writes log lines to the stdin of a shell command, which is restarted if it exits.
+//!     Quotes inside of the command need to be escaped, e.g. `pipe:logger -t \"my app\"`.
+//!     
+//!     Use `tcp+json://host:port`, `udp+json://host:port` or `unix+json:path` to send one JSON object per log message instead of text lines.
+//!     
+//!     `gelf+udp://host:port` and `gelf+tcp://host:port` send log messages to a GELF input, e.g. of Graylog.
+//!     Use `gelf+udp+gzip://host:port` to compress the messages.
+//!     
+//!     `otlp:url` exports log records to an OpenTelemetry collector with OTLP/HTTP, `otlp+json:url` uses JSON instead of protobuf encoding.
+//!     
+//!     Options can be appended after a `;` as comma separated `key=value` pairs, flags like `truncate` don't need a value.
+//!     Paths and commands containing `:` or `;` can be quoted like `file:"/path/with:colon"`, or escaped like `file:/path/with\:colon`.
+//!     * file: format=text|json, create_dirs, mode=640, truncate, append, lock=fail|wait|suffix|none, fsync=always|errors|never|5s, min_free=1GB, max_size=500MB
+//!     * tcp, udp, unix: format=text|json
+//!     * gelf+udp: compress
+//!     * otlp: encoding=protobuf|json
+//!     * all destinations: include, exclude, include_target, exclude_target to filter by message or target, with a substring like `exclude=healthcheck` or a regular expression like `exclude=/^GET /health/`. A `~` prefix keeps the rest a substring, e.g. `exclude=~/health/`
+//!     
+//!     Format: destination | level_filter:destination, optionally followed by ";options"
+//!     * level_filter = level | level..level | =level, e.g. "DEBUG..INFO" to log only debug and info messages, or "=WARN" to log only warnings
+//!     * level = "OFF" | "ERROR" | "WARN" | "INFO" | "DEBUG" | "TRACE", or an alias like "warning", "fatal", "verbose", or "0" to "5"
+//!     * destination = "stderr" | "syslog" | "file:path" | "fd:number" | "pipe:command" | "memory:name" | "ringbuffer:capacity:sink" | "tcp://host:port" | "udp://host:port" | "unix:path" | "gelf+udp://host:port" | "gelf+tcp://host:port" | "otlp:url" | "none"
 //!     
 //!     Examples:
 //!     * `--log syslog`
@@ -108,7 +195,20 @@
 //!     * `--log file:/path/to/file`
 //!     * `--log INFO:stderr`
 //!     * `--log DEBUG:file:/path/to/file`
+//!     * `--log 'DEBUG:file:/path/to/file;format=json,create_dirs,max_size=1GB'`
+//!     * `--log 'file:~/logs/{exe}-{pid}.log'`
+//!     * `--log INFO:fd:3`
+//!     * `--log "INFO:pipe:gzip > /path/to/file.gz"`
 //!     * `--log TRACE:syslog`
+//!     * `--log OFF:stderr`
+//!     * `--log DEBUG..INFO:file:/path/to/file`
+//!     * `--log =WARN:stderr`
+//!     * `--log 'INFO:stderr;exclude=/healthcheck|metrics/'`
+//!     * `--log TRACE:ringbuffer:1000:file:/path/to/file`
+//!     * `--log INFO:tcp://localhost:9000`
+//!     * `--log INFO:unix:/path/to/socket`
+//!     * `--log INFO:gelf+udp://localhost:12201`
+//!     * `--log INFO:otlp:http://localhost:4318`
 //!     * `--log none`
 //!
 //! -h, --help
@@ -124,11 +224,42 @@
 // We need to add explicit links because our `gen_readme.sh` script requires them.
 #![allow(rustdoc::redundant_explicit_links)]
 
+mod callback;
+mod child;
 mod clap;
 mod config;
 mod fern;
+mod file;
+mod filter;
+mod gelf;
+mod line_logger;
+mod memory;
+mod network;
+mod options;
+#[cfg(feature = "otlp")]
+mod otlp;
+mod panic_hook;
 mod parser;
+mod path_template;
+mod pipe;
+mod ring_buffer;
+mod sink;
+mod suggestions;
+mod tokenizer;
+#[cfg(feature = "tracing")]
+mod tracing_layer;
+mod utils;
 
+pub use callback::{CallbackSink, LogRecord};
 pub use clap::LogArgs;
-pub use config::{LogDestination, LogDestinationConfig, LoggingConfig};
-pub use fern::_init_logging;
+pub use config::{
+    FileLock, FileOptions, FsyncPolicy, GelfTransport, InitOptions, LogDestination,
+    LogDestinationConfig, LogFilter, LogFilterField, LogFilterKind, LogFilterPattern, LogFormat,
+    LoggingConfig, OtlpEncoding, PanicHook,
+};
+pub use fern::{_init_logging, LoggingGuard};
+pub use memory::{MemoryBuffer, MemoryRecord};
+pub use parser::ParseError;
+pub use sink::{LogSink, register_sink};
+#[cfg(feature = "tracing")]
+pub use tracing_layer::{_tracing_layer, LogflagLayer};
@@ -0,0 +1,196 @@
+use std::sync::Mutex;
+use std::sync::mpsc::{RecvTimeoutError, Sender, SyncSender, TrySendError};
+use std::time::{Duration, Instant};
+
+use super::utils::{ignore_log_error, lock, send_until};
+
+/// Lines queued for a [BackgroundLineWriter] before further lines are dropped
+const QUEUE_CAPACITY: usize = 10_000;
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Something that (already formatted) log lines can be written to, e.g. a network connection.
+pub trait LineWriter: Send + 'static {
+    /// Write a single log line. The line doesn't include a trailing newline, implementations need to add it if required.
+    fn write_line(&mut self, line: &str) -> std::io::Result<()>;
+
+    /// Flush any buffered log lines.
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A [log::Log] implementation that writes each (already formatted) record as a line to a [LineWriter].
+///
+/// Unlike chaining a [std::io::Write] into a [fern::Dispatch], this hands each line to the writer in one piece,
+/// which is needed for message-based destinations like UDP and allows writers to reconnect between lines.
+pub struct LineLogger<W: LineWriter> {
+    writer: Mutex<W>,
+}
+
+impl<W: LineWriter> LineLogger<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Box the logger so it can be chained into a [fern::Dispatch].
+    pub fn boxed(writer: W) -> Box<dyn log::Log> {
+        Box::new(Self::new(writer))
+    }
+}
+
+impl<W: LineWriter> log::Log for LineLogger<W> {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let line = record.args().to_string();
+        ignore_log_error(lock(&self.writer).write_line(&line));
+    }
+
+    fn flush(&self) {
+        ignore_log_error(lock(&self.writer).flush());
+    }
+}
+
+/// A [LineWriter] that hands lines to another writer on a background thread.
+///
+/// Used for writers that can block, e.g. while resolving a host name, connecting, or writing to a peer that stopped reading,
+/// so logging never waits for them. Lines are dropped while the queue is full.
+pub struct BackgroundLineWriter {
+    sender: SyncSender<Message>,
+}
+
+enum Message {
+    Line(String),
+    Flush(Sender<std::io::Result<()>>),
+}
+
+impl BackgroundLineWriter {
+    pub fn new<W: LineWriter>(mut writer: W) -> Self {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(QUEUE_CAPACITY);
+        // The thread ends once the sender is dropped and all queued lines are written
+        std::thread::spawn(move || {
+            for message in receiver {
+                match message {
+                    Message::Line(line) => ignore_log_error(writer.write_line(&line)),
+                    Message::Flush(done_sender) => {
+                        let _ = done_sender.send(writer.flush());
+                    }
+                }
+            }
+        });
+        Self { sender }
+    }
+}
+
+impl LineWriter for BackgroundLineWriter {
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        self.sender
+            .try_send(Message::Line(line.to_string()))
+            .map_err(|err| match err {
+                TrySendError::Full(_) => {
+                    std::io::Error::new(std::io::ErrorKind::WouldBlock, "Log line queue is full")
+                }
+                TrySendError::Disconnected(_) => {
+                    std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Log writer thread stopped")
+                }
+            })
+    }
+
+    /// Wait until all queued lines are written and flushed, but not longer than a few seconds.
+    /// This is what delivers the queued lines before a short-lived process exits, see [LoggingGuard](crate::LoggingGuard).
+    fn flush(&mut self) -> std::io::Result<()> {
+        let deadline = Instant::now() + FLUSH_TIMEOUT;
+        let (done_sender, done_receiver) = std::sync::mpsc::channel();
+        // If the queue is full, wait for the writer thread to make room instead of giving up right away
+        send_until(&self.sender, Message::Flush(done_sender), deadline).map_err(
+            |err| match err {
+                TrySendError::Full(_) => std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "Flushing log lines timed out",
+                ),
+                TrySendError::Disconnected(_) => {
+                    std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Log writer thread stopped")
+                }
+            },
+        )?;
+        done_receiver
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .map_err(|err| match err {
+                RecvTimeoutError::Timeout => std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "Flushing log lines timed out",
+                ),
+                RecvTimeoutError::Disconnected => {
+                    std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Log writer thread stopped")
+                }
+            })?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::mpsc::Receiver;
+
+    /// Collects lines, but only after the test allows it to write each one
+    struct BlockingWriter {
+        lines: Arc<Mutex<Vec<String>>>,
+        permits: Receiver<()>,
+    }
+
+    impl LineWriter for BlockingWriter {
+        fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+            self.permits.recv().unwrap();
+            lock(&self.lines).push(line.to_string());
+            Ok(())
+        }
+    }
+
+    fn blocking_writer() -> (BackgroundLineWriter, Arc<Mutex<Vec<String>>>, Sender<()>) {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let (permit_sender, permits) = std::sync::mpsc::channel();
+        let writer = BackgroundLineWriter::new(BlockingWriter {
+            lines: lines.clone(),
+            permits,
+        });
+        (writer, lines, permit_sender)
+    }
+
+    #[test]
+    fn writes_lines_in_background() {
+        let (mut writer, lines, permit_sender) = blocking_writer();
+        writer.write_line("first line").unwrap();
+        writer.write_line("second line").unwrap();
+        assert!(lock(&lines).is_empty());
+        permit_sender.send(()).unwrap();
+        permit_sender.send(()).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(vec!["first line", "second line"], *lock(&lines));
+    }
+
+    #[test]
+    fn drops_lines_while_queue_is_full() {
+        let (mut writer, lines, permit_sender) = blocking_writer();
+        let mut written = 0;
+        let error = loop {
+            match writer.write_line("line") {
+                Ok(()) => written += 1,
+                Err(err) => break err,
+            }
+        };
+        assert_eq!(std::io::ErrorKind::WouldBlock, error.kind());
+        // The blocked writer thread may already have taken a line from the queue
+        assert!((QUEUE_CAPACITY..=QUEUE_CAPACITY + 1).contains(&written));
+        for _ in 0..written {
+            permit_sender.send(()).unwrap();
+        }
+        // Flushing waits until there's room in the queue again
+        writer.flush().unwrap();
+        assert_eq!(written, lock(&lines).len());
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use regex::Regex;
+
+use super::utils::lock;
+
+/// All memory buffers that were created so far, by name.
+static BUFFERS: LazyLock<Mutex<HashMap<String, MemoryBuffer>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// A log record captured by the `memory:name` log destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryRecord {
+    /// The level of the log record
+    pub level: log::Level,
+
+    /// The target of the log record
+    pub target: String,
+
+    /// The formatted log line, in the same format as it would be written to a log file
+    pub line: String,
+}
+
+/// A shared in-memory buffer that captures the records logged to the `memory:name` log destination.
+///
+/// This is meant for tests. Instead of spawning your application and parsing its stderr or log files,
+/// you can run it with `--log memory:name` and query the logged records with [MemoryBuffer::get].
+///
+/// # Example
+/// ```rust
+/// use clap_logflag::{LogDestination, LogDestinationConfig, LoggingConfig, MemoryBuffer};
+/// use log::{Level, LevelFilter};
+///
+/// let _logging = clap_logflag::init_logging!(
+///     LoggingConfig::new(vec![LogDestinationConfig::new(
+///         LogDestination::Memory("my-test".to_string()),
+///         None,
+///     )]),
+///     LevelFilter::Info,
+/// );
+///
+/// log::info!("Connected to server 127.0.0.1");
+///
+/// MemoryBuffer::get("my-test").assert_logged(Level::Info, r"Connected to server \d+\.\d+\.\d+\.\d+");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBuffer {
+    records: Arc<Mutex<Vec<MemoryRecord>>>,
+}
+
+impl MemoryBuffer {
+    /// Get the memory buffer with the given name. If it doesn't exist yet, it is created.
+    ///
+    /// All log destinations `memory:name` with the same name write into the same buffer.
+    pub fn get(name: &str) -> Self {
+        lock(&BUFFERS).entry(name.to_string()).or_default().clone()
+    }
+
+    /// Get a copy of all records captured so far.
+    pub fn records(&self) -> Vec<MemoryRecord> {
+        lock(&self.records).clone()
+    }
+
+    /// Get the formatted log lines of all records captured so far.
+    pub fn lines(&self) -> Vec<String> {
+        lock(&self.records)
+            .iter()
+            .map(|record| record.line.clone())
+            .collect()
+    }
+
+    /// Remove all captured records.
+    pub fn clear(&self) {
+        lock(&self.records).clear();
+    }
+
+    /// Return true if a record with the given level was captured whose formatted log line matches the given regex.
+    ///
+    /// # Panics
+    /// Panics if `regex` isn't a valid regular expression.
+    pub fn contains(&self, level: log::Level, regex: &str) -> bool {
+        let regex = Regex::new(regex).expect("Invalid regex");
+        lock(&self.records)
+            .iter()
+            .any(|record| record.level == level && regex.is_match(&record.line))
+    }
+
+    /// Assert that a record with the given level was captured whose formatted log line matches the given regex.
+    ///
+    /// # Panics
+    /// Panics if no such record was captured, or if `regex` isn't a valid regular expression.
+    #[track_caller]
+    pub fn assert_logged(&self, level: log::Level, regex: &str) {
+        assert!(
+            self.contains(level, regex),
+            "Expected a {level} log matching `{regex}`, but it wasn't logged. Logged were:\n{}",
+            self.lines().join("\n"),
+        );
+    }
+
+    /// Assert that no record with the given level was captured whose formatted log line matches the given regex.
+    ///
+    /// # Panics
+    /// Panics if such a record was captured, or if `regex` isn't a valid regular expression.
+    #[track_caller]
+    pub fn assert_not_logged(&self, level: log::Level, regex: &str) {
+        assert!(
+            !self.contains(level, regex),
+            "Expected no {level} log matching `{regex}`, but it was logged. Logged were:\n{}",
+            self.lines().join("\n"),
+        );
+    }
+
+    fn push(&self, record: MemoryRecord) {
+        lock(&self.records).push(record);
+    }
+}
+
+/// A [log::Log] implementation that writes the (already formatted) records into a [MemoryBuffer].
+pub struct MemoryLogger {
+    buffer: MemoryBuffer,
+}
+
+impl MemoryLogger {
+    pub fn new(name: &str) -> Self {
+        Self {
+            buffer: MemoryBuffer::get(name),
+        }
+    }
+}
+
+impl log::Log for MemoryLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.buffer.push(MemoryRecord {
+            level: record.level(),
+            target: record.target().to_string(),
+            line: record.args().to_string(),
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::{Level, Log as _};
+
+    fn log(logger: &MemoryLogger, level: Level, message: &str) {
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("{message}"))
+                .level(level)
+                .target("my-test")
+                .build(),
+        );
+    }
+
+    #[test]
+    fn same_name_shares_buffer() {
+        let logger = MemoryLogger::new("memory::tests::same_name_shares_buffer");
+        log(&logger, Level::Info, "first message");
+        log(&logger, Level::Warn, "second message");
+        assert_eq!(
+            vec![
+                MemoryRecord {
+                    level: Level::Info,
+                    target: "my-test".to_string(),
+                    line: "first message".to_string(),
+                },
+                MemoryRecord {
+                    level: Level::Warn,
+                    target: "my-test".to_string(),
+                    line: "second message".to_string(),
+                },
+            ],
+            MemoryBuffer::get("memory::tests::same_name_shares_buffer").records(),
+        );
+    }
+
+    #[test]
+    fn different_names_dont_share_buffer() {
+        let logger = MemoryLogger::new("memory::tests::different_names_dont_share_buffer_1");
+        log(&logger, Level::Info, "message");
+        assert!(
+            MemoryBuffer::get("memory::tests::different_names_dont_share_buffer_2")
+                .records()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn contains() {
+        let logger = MemoryLogger::new("memory::tests::contains");
+        log(&logger, Level::Info, "Connected to 127.0.0.1");
+        let buffer = MemoryBuffer::get("memory::tests::contains");
+        assert!(buffer.contains(Level::Info, r"Connected to \d+\.0\.0\.1"));
+        assert!(buffer.contains(Level::Info, "Connected"));
+        assert!(!buffer.contains(Level::Warn, "Connected"));
+        assert!(!buffer.contains(Level::Info, "Disconnected"));
+        buffer.assert_logged(Level::Info, "Connected");
+        buffer.assert_not_logged(Level::Error, "Connected");
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected a WARN log matching `Connected`, but it wasn't logged")]
+    fn assert_logged_fails() {
+        let logger = MemoryLogger::new("memory::tests::assert_logged_fails");
+        log(&logger, Level::Info, "Connected");
+        MemoryBuffer::get("memory::tests::assert_logged_fails")
+            .assert_logged(Level::Warn, "Connected");
+    }
+
+    #[test]
+    fn clear() {
+        let logger = MemoryLogger::new("memory::tests::clear");
+        log(&logger, Level::Info, "message");
+        let buffer = MemoryBuffer::get("memory::tests::clear");
+        assert_eq!(vec!["message".to_string()], buffer.lines());
+        buffer.clear();
+        assert!(buffer.lines().is_empty());
+    }
+}
@@ -0,0 +1,460 @@
+use std::io::{ErrorKind, Write as _};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs as _, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::{UnixDatagram, UnixStream};
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context as _, Result};
+
+use super::line_logger::LineWriter;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+/// A peer that doesn't read for this long is treated like a broken connection
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Writes log lines to a connection to a TCP server or a unix domain socket.
+///
+/// If the connection can't be established or breaks, it reconnects with exponential backoff.
+/// Log lines written while there is no connection and the backoff hasn't expired yet are dropped.
+///
+/// Connecting and writing block, so loggers should wrap it in a [BackgroundLineWriter](super::line_logger::BackgroundLineWriter).
+pub struct ReconnectingLineWriter {
+    target: Target,
+    delimiter: u8,
+    connection: Option<Connection>,
+    backoff: Backoff,
+}
+
+enum Target {
+    Tcp(String),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+enum Connection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    UnixStream(UnixStream),
+    #[cfg(unix)]
+    UnixDatagram(UnixDatagram),
+}
+
+impl ReconnectingLineWriter {
+    /// Create a writer that sends newline separated log lines to a TCP server at the given `host:port` address.
+    /// The connection is only established when the first line is written,
+    /// so the application doesn't fail to start if the receiving end isn't up yet.
+    pub fn tcp(address: &str) -> Self {
+        Self::new(Target::Tcp(address.to_string()))
+    }
+
+    /// Create a writer that sends log lines to the unix domain socket at the given path.
+    /// Both stream sockets (newline separated lines) and datagram sockets (one datagram per line) are supported,
+    /// which kind of socket it is gets detected when connecting.
+    #[cfg(unix)]
+    pub fn unix(path: &Path) -> Self {
+        Self::new(Target::Unix(path.to_path_buf()))
+    }
+
+    fn new(target: Target) -> Self {
+        Self {
+            target,
+            delimiter: b'\n',
+            connection: None,
+            backoff: Backoff::new(),
+        }
+    }
+
+    /// Use a different byte than a newline to separate lines on stream connections, e.g. a null byte for GELF.
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    fn connection(&mut self) -> std::io::Result<&mut Connection> {
+        if self.connection.is_none() {
+            if !self.backoff.may_attempt() {
+                return Err(std::io::Error::new(
+                    ErrorKind::NotConnected,
+                    "Waiting for reconnect backoff",
+                ));
+            }
+            match self.target.connect() {
+                Ok(connection) => {
+                    self.backoff.reset();
+                    self.connection = Some(connection);
+                }
+                Err(err) => {
+                    self.backoff.failed();
+                    return Err(err);
+                }
+            }
+        }
+        Ok(self.connection.as_mut().expect("We just connected"))
+    }
+}
+
+impl Target {
+    fn connect(&self) -> std::io::Result<Connection> {
+        match self {
+            Target::Tcp(address) => {
+                let stream = connect_tcp(address)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(Connection::Tcp(stream))
+            }
+            #[cfg(unix)]
+            Target::Unix(path) => match UnixStream::connect(path) {
+                Ok(stream) => {
+                    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                    Ok(Connection::UnixStream(stream))
+                }
+                Err(stream_error) => {
+                    // Connecting a stream to a datagram socket fails, so try a datagram socket before giving up.
+                    let datagram = UnixDatagram::unbound()?;
+                    match datagram.connect(path) {
+                        Ok(()) => Ok(Connection::UnixDatagram(datagram)),
+                        Err(_) => Err(stream_error),
+                    }
+                }
+            },
+        }
+    }
+}
+
+fn connect_tcp(address: &str) -> std::io::Result<TcpStream> {
+    let mut last_error = None;
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_error = Some(err),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| std::io::Error::new(ErrorKind::NotFound, "Address didn't resolve")))
+}
+
+impl Connection {
+    fn write_line(&mut self, line: &str, delimiter: u8) -> std::io::Result<()> {
+        // Write the line and its delimiter at once so a broken connection can't leave a line without delimiter behind
+        let mut buffer = Vec::with_capacity(line.len() + 1);
+        buffer.extend_from_slice(line.as_bytes());
+        buffer.push(delimiter);
+        match self {
+            Connection::Tcp(stream) => stream.write_all(&buffer),
+            #[cfg(unix)]
+            Connection::UnixStream(stream) => stream.write_all(&buffer),
+            #[cfg(unix)]
+            Connection::UnixDatagram(datagram) => datagram.send(line.as_bytes()).map(|_| ()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Connection::UnixStream(stream) => stream.flush(),
+            #[cfg(unix)]
+            Connection::UnixDatagram(_) => Ok(()),
+        }
+    }
+}
+
+impl LineWriter for ReconnectingLineWriter {
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let had_connection = self.connection.is_some();
+        let delimiter = self.delimiter;
+        match self.connection()?.write_line(line, delimiter) {
+            Ok(()) => Ok(()),
+            Err(_) if had_connection => {
+                // The connection broke, e.g. because the receiving end restarted. Reconnect and try once more.
+                self.connection = None;
+                let result = self.connection()?.write_line(line, delimiter);
+                if result.is_err() {
+                    self.connection = None;
+                }
+                result
+            }
+            Err(err) => {
+                self.connection = None;
+                Err(err)
+            }
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.connection {
+            Some(connection) => connection.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Exponential backoff for reconnect attempts
+pub struct Backoff {
+    next_attempt: Instant,
+    delay: Duration,
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Self {
+            next_attempt: Instant::now(),
+            delay: INITIAL_BACKOFF,
+        }
+    }
+
+    pub fn may_attempt(&self) -> bool {
+        Instant::now() >= self.next_attempt
+    }
+
+    pub fn failed(&mut self) {
+        self.next_attempt = Instant::now() + self.delay;
+        self.delay = (self.delay * 2).min(MAX_BACKOFF);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// Sends each log line as a separate UDP datagram.
+pub struct UdpLineWriter {
+    socket: UdpSocket,
+}
+
+impl UdpLineWriter {
+    pub fn new(address: &str) -> Result<Self> {
+        Ok(Self {
+            socket: connect_udp(address)?,
+        })
+    }
+}
+
+/// Create a UDP socket that sends to the given `host:port` address.
+pub fn connect_udp(address: &str) -> Result<UdpSocket> {
+    let target = address
+        .to_socket_addrs()
+        .with_context(|| format!("Failed to resolve address `{address}`"))?
+        .next()
+        .with_context(|| format!("Address `{address}` didn't resolve"))?;
+    let bind_address: SocketAddr = if target.is_ipv4() {
+        "0.0.0.0:0".parse().unwrap()
+    } else {
+        "[::]:0".parse().unwrap()
+    };
+    let socket = UdpSocket::bind(bind_address)?;
+    socket.connect(target)?;
+    Ok(socket)
+}
+
+impl LineWriter for UdpLineWriter {
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        self.socket.send(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead as _, BufReader};
+    use std::net::TcpListener;
+
+    fn read_lines(listener: &TcpListener, count: usize) -> Vec<String> {
+        let (stream, _) = listener.accept().unwrap();
+        BufReader::new(stream)
+            .lines()
+            .take(count)
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn tcp_writes_lines() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut writer = ReconnectingLineWriter::tcp(&listener.local_addr().unwrap().to_string());
+        writer.write_line("first line").unwrap();
+        writer.write_line("second line").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(vec!["first line", "second line"], read_lines(&listener, 2));
+    }
+
+    #[test]
+    fn tcp_with_delimiter() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut writer = ReconnectingLineWriter::tcp(&listener.local_addr().unwrap().to_string())
+            .with_delimiter(b'\0');
+        writer.write_line("first line").unwrap();
+        writer.write_line("second line").unwrap();
+        drop(writer);
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = Vec::new();
+        std::io::Read::read_to_end(&mut stream, &mut received).unwrap();
+        assert_eq!(b"first line\0second line\0".as_slice(), received);
+    }
+
+    #[test]
+    fn tcp_reconnects_after_connection_broke() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut writer = ReconnectingLineWriter::tcp(&listener.local_addr().unwrap().to_string());
+        writer.write_line("first line").unwrap();
+        {
+            // Accept and immediately close the connection
+            let (stream, _) = listener.accept().unwrap();
+            stream.shutdown(std::net::Shutdown::Both).unwrap();
+        }
+        // Writes to a closed connection only start failing after the peer's reset was received,
+        // so keep writing until the writer notices and reconnects.
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let accept_thread = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            sender.send(()).unwrap();
+            BufReader::new(stream)
+                .lines()
+                .map(Result::unwrap)
+                .find(|line| line == "after reconnect")
+        });
+        for _ in 0..100 {
+            let _ = writer.write_line("retry");
+            if receiver.try_recv().is_ok() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        writer.write_line("after reconnect").unwrap();
+        drop(writer);
+        assert_eq!(
+            Some("after reconnect".to_string()),
+            accept_thread.join().unwrap()
+        );
+    }
+
+    #[test]
+    fn tcp_drops_lines_while_backing_off() {
+        // Get a port that nobody listens on
+        let address = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().to_string()
+        };
+        let mut writer = ReconnectingLineWriter::tcp(&address);
+        let error = writer.write_line("line").unwrap_err();
+        assert_eq!(ErrorKind::ConnectionRefused, error.kind());
+        // Directly afterwards, we're in the backoff period and don't even try
+        let error = writer.write_line("line").unwrap_err();
+        assert_eq!(ErrorKind::NotConnected, error.kind());
+    }
+
+    #[test]
+    fn tcp_reconnects_if_peer_doesnt_read() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut writer = ReconnectingLineWriter::tcp(&listener.local_addr().unwrap().to_string());
+        let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let write_thread = std::thread::spawn({
+            let stop = stop.clone();
+            move || {
+                // Fill the socket buffers until writing times out instead of blocking forever
+                let line = "x".repeat(1024 * 1024);
+                while !stop.load(std::sync::atomic::Ordering::Relaxed) {
+                    let _ = writer.write_line(&line);
+                }
+            }
+        });
+        let _stalled = listener.accept().unwrap();
+        let _reconnected = listener.accept().unwrap();
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        write_thread.join().unwrap();
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_max() {
+        let mut backoff = Backoff::new();
+        assert!(backoff.may_attempt());
+        backoff.failed();
+        assert!(!backoff.may_attempt());
+        assert_eq!(INITIAL_BACKOFF * 2, backoff.delay);
+        for _ in 0..20 {
+            backoff.failed();
+        }
+        assert_eq!(MAX_BACKOFF, backoff.delay);
+        backoff.reset();
+        assert!(backoff.may_attempt());
+        assert_eq!(INITIAL_BACKOFF, backoff.delay);
+    }
+
+    #[test]
+    fn udp_sends_datagrams() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut writer = UdpLineWriter::new(&receiver.local_addr().unwrap().to_string()).unwrap();
+        writer.write_line("first line").unwrap();
+        writer.write_line("second line").unwrap();
+        let mut buf = [0; 1024];
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(b"first line", &buf[..len]);
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(b"second line", &buf[..len]);
+    }
+
+    #[test]
+    fn udp_invalid_address() {
+        assert!(UdpLineWriter::new("not an address").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_stream_writes_lines() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("socket");
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let mut writer = ReconnectingLineWriter::unix(&path);
+        writer.write_line("first line").unwrap();
+        writer.write_line("second line").unwrap();
+        writer.flush().unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let lines: Vec<String> = BufReader::new(stream)
+            .lines()
+            .take(2)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(vec!["first line", "second line"], lines);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_datagram_sends_datagrams() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("socket");
+        let receiver = UnixDatagram::bind(&path).unwrap();
+        let mut writer = ReconnectingLineWriter::unix(&path);
+        writer.write_line("first line").unwrap();
+        writer.write_line("second line").unwrap();
+        let mut buf = [0; 1024];
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(b"first line", &buf[..len]);
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(b"second line", &buf[..len]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_reconnects_once_socket_exists() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("socket");
+        let mut writer = ReconnectingLineWriter::unix(&path);
+        let error = writer.write_line("dropped line").unwrap_err();
+        assert_eq!(ErrorKind::NotFound, error.kind());
+
+        let receiver = UnixDatagram::bind(&path).unwrap();
+        // Wait for the backoff to expire
+        std::thread::sleep(INITIAL_BACKOFF);
+        writer.write_line("line after reconnect").unwrap();
+        let mut buf = [0; 1024];
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(b"line after reconnect", &buf[..len]);
+    }
+}
@@ -0,0 +1,526 @@
+use std::time::Duration;
+
+use super::config::{
+    FileLock, FsyncPolicy, GelfTransport, LogDestination, LogDestinationConfig, LogFilter,
+    LogFilterField, LogFilterKind, LogFilterPattern, LogFormat, OtlpEncoding,
+};
+use super::parser::ParseError;
+use super::tokenizer::{split_once_unquoted, split_unquoted, unquote};
+
+const FILE_OPTIONS: &[&str] = &[
+    "format",
+    "create_dirs",
+    "mode",
+    "truncate",
+    "append",
+    "lock",
+    "fsync",
+    "min_free",
+    "max_size",
+];
+const NETWORK_OPTIONS: &[&str] = &["format"];
+const GELF_UDP_OPTIONS: &[&str] = &["compress"];
+const OTLP_OPTIONS: &[&str] = &["encoding"];
+/// Options that every destination supports. They can be given multiple times.
+const FILTER_OPTIONS: &[&str] = &["include", "exclude", "include_target", "exclude_target"];
+
+/// Apply options like `format=json,truncate` to a log destination.
+///
+/// The filter options `include`, `exclude`, `include_target` and `exclude_target` are added to the filters of the config.
+/// Otherwise, each destination only accepts the options that make sense for it. The options of a ring buffer apply to its sink.
+pub fn apply_options(config: &mut LogDestinationConfig, options: &str) -> Result<(), ParseError> {
+    let options = parse_key_values(options)?;
+    let mut destination_options: Vec<(&str, Option<&str>)> = Vec::new();
+    for (key, value) in &options {
+        match parse_filter(key, value.as_deref())? {
+            Some(filter) => config.filters.push(filter),
+            None => destination_options.push((key.as_str(), value.as_deref())),
+        }
+    }
+    if destination_options.is_empty() {
+        return Ok(());
+    }
+    apply_key_values(&mut config.destination, &destination_options)
+}
+
+/// Parse a filter option like `exclude=/healthcheck/`, or return `None` if the key isn't a filter option.
+/// Values between slashes are regular expressions, other values are substrings.
+/// A `~` prefix keeps the rest of the value a substring, even if it's between slashes.
+fn parse_filter(key: &str, value: Option<&str>) -> Result<Option<LogFilter>, ParseError> {
+    let (kind, field) = match key {
+        "include" => (LogFilterKind::Include, LogFilterField::Message),
+        "exclude" => (LogFilterKind::Exclude, LogFilterField::Message),
+        "include_target" => (LogFilterKind::Include, LogFilterField::Target),
+        "exclude_target" => (LogFilterKind::Exclude, LogFilterField::Target),
+        _ => return Ok(None),
+    };
+    let value = required_value(key, value)?;
+    if let Some(substring) = value.strip_prefix('~') {
+        return Ok(Some(LogFilter {
+            kind,
+            field,
+            pattern: LogFilterPattern::Substring(substring.to_string()),
+        }));
+    }
+    let pattern = match value
+        .strip_prefix('/')
+        .and_then(|value| value.strip_suffix('/'))
+    {
+        Some(regex) => {
+            regex::Regex::new(regex).map_err(|_| {
+                invalid_value(
+                    key,
+                    value,
+                    "a substring, or a regular expression like `/^GET /health/`",
+                )
+            })?;
+            LogFilterPattern::Regex(regex.to_string())
+        }
+        None => LogFilterPattern::Substring(value.to_string()),
+    };
+    Ok(Some(LogFilter {
+        kind,
+        field,
+        pattern,
+    }))
+}
+
+fn apply_key_values(
+    destination: &mut LogDestination,
+    options: &[(&str, Option<&str>)],
+) -> Result<(), ParseError> {
+    let name = destination_name(destination).to_string();
+    match destination {
+        LogDestination::File { options: file, .. } => {
+            for &(key, value) in options {
+                match key {
+                    "format" => file.format = parse_format(key, value)?,
+                    "create_dirs" => file.create_dirs = parse_bool(key, value)?,
+                    "mode" => file.mode = Some(parse_mode(key, value)?),
+                    "truncate" => file.truncate = parse_bool(key, value)?,
+                    "append" => file.truncate = !parse_bool(key, value)?,
+                    "lock" => file.lock = parse_lock(key, value)?,
+                    "fsync" => file.fsync = parse_fsync(key, value)?,
+                    "min_free" => file.min_free_space = Some(parse_size(key, value)?),
+                    "max_size" => file.max_size = Some(parse_size(key, value)?),
+                    _ => return Err(unknown_option(&name, key, FILE_OPTIONS)),
+                }
+            }
+        }
+        LogDestination::Tcp { format, .. }
+        | LogDestination::Udp { format, .. }
+        | LogDestination::Unix { format, .. } => {
+            for &(key, value) in options {
+                match key {
+                    "format" => *format = parse_format(key, value)?,
+                    _ => return Err(unknown_option(&name, key, NETWORK_OPTIONS)),
+                }
+            }
+        }
+        LogDestination::Gelf {
+            transport: GelfTransport::Udp { compress },
+            ..
+        } => {
+            for &(key, value) in options {
+                match key {
+                    "compress" => *compress = parse_bool(key, value)?,
+                    _ => return Err(unknown_option(&name, key, GELF_UDP_OPTIONS)),
+                }
+            }
+        }
+        LogDestination::Otlp { encoding, .. } => {
+            for &(key, value) in options {
+                match key {
+                    "encoding" => {
+                        *encoding = match required_value(key, value)? {
+                            "protobuf" => OtlpEncoding::Protobuf,
+                            "json" => OtlpEncoding::Json,
+                            value => return Err(invalid_value(key, value, "protobuf or json")),
+                        }
+                    }
+                    _ => return Err(unknown_option(&name, key, OTLP_OPTIONS)),
+                }
+            }
+        }
+        LogDestination::RingBuffer { sink, .. } => {
+            return apply_key_values(sink, options);
+        }
+        _ => {
+            // Only the filter options are supported, and they're already handled
+            return Err(unknown_option(&name, options[0].0, &[]));
+        }
+    }
+    Ok(())
+}
+
+fn destination_name(destination: &LogDestination) -> &str {
+    match destination {
+        LogDestination::Stderr => "stderr",
+        LogDestination::File { .. } => "file",
+        LogDestination::Syslog => "syslog",
+        LogDestination::Memory(_) => "memory",
+        LogDestination::Fd(_) => "fd",
+        LogDestination::Pipe(_) => "pipe",
+        LogDestination::RingBuffer { .. } => "ringbuffer",
+        LogDestination::Tcp { .. } => "tcp",
+        LogDestination::Udp { .. } => "udp",
+        LogDestination::Unix { .. } => "unix",
+        LogDestination::Gelf {
+            transport: GelfTransport::Udp { .. },
+            ..
+        } => "gelf+udp",
+        LogDestination::Gelf {
+            transport: GelfTransport::Tcp,
+            ..
+        } => "gelf+tcp",
+        LogDestination::Otlp { .. } => "otlp",
+        LogDestination::Custom { scheme, .. } => scheme,
+    }
+}
+
+/// Parse `key=value,flag` into key-value pairs, rejecting empty and duplicate keys. Values can be quoted like paths.
+fn parse_key_values(options: &str) -> Result<Vec<(String, Option<String>)>, ParseError> {
+    let mut result: Vec<(String, Option<String>)> = Vec::new();
+    for option in split_unquoted(options, ',')? {
+        let (key, value) = split_once_unquoted(option, '=')?;
+        let key = unquote(key.trim()).into_owned();
+        let value = value.map(|value| unquote(value.trim()).into_owned());
+        if key.is_empty() {
+            return Err(ParseError::new(format!(
+                "Invalid log destination option `{option}`. Options are written as `key=value`, separated by commas, e.g. `file:/path/to/file;format=json,truncate`"
+            )));
+        }
+        if !FILTER_OPTIONS.contains(&key.as_str())
+            && result.iter().any(|(existing, _)| *existing == key)
+        {
+            return Err(ParseError::new(format!(
+                "Log destination option `{key}` is given multiple times"
+            )));
+        }
+        result.push((key, value));
+    }
+    Ok(result)
+}
+
+fn unknown_option(destination: &str, key: &str, choices: &[&str]) -> ParseError {
+    ParseError::new(format!(
+        "Unknown option `{key}` for the {destination} log destination. Choose {}",
+        choices
+            .iter()
+            .chain(FILTER_OPTIONS)
+            .copied()
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+fn invalid_value(key: &str, value: &str, expected: &str) -> ParseError {
+    ParseError::new(format!(
+        "Invalid value `{value}` for log destination option `{key}`. Expected {expected}"
+    ))
+}
+
+fn required_value<'a>(key: &str, value: Option<&'a str>) -> Result<&'a str, ParseError> {
+    value.ok_or_else(|| {
+        ParseError::new(format!(
+            "Log destination option `{key}` requires a value, e.g. `{key}=...`"
+        ))
+    })
+}
+
+/// A flag without a value, e.g. `truncate`, means `true`.
+fn parse_bool(key: &str, value: Option<&str>) -> Result<bool, ParseError> {
+    match value {
+        None | Some("true") => Ok(true),
+        Some("false") => Ok(false),
+        Some(value) => Err(invalid_value(key, value, "true or false")),
+    }
+}
+
+fn parse_format(key: &str, value: Option<&str>) -> Result<LogFormat, ParseError> {
+    match required_value(key, value)? {
+        "text" => Ok(LogFormat::Text),
+        "json" => Ok(LogFormat::Json),
+        value => Err(invalid_value(key, value, "text or json")),
+    }
+}
+
+fn parse_mode(key: &str, value: Option<&str>) -> Result<u32, ParseError> {
+    let value = required_value(key, value)?;
+    u32::from_str_radix(value, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| invalid_value(key, value, "an octal file mode like 640"))
+}
+
+fn parse_lock(key: &str, value: Option<&str>) -> Result<FileLock, ParseError> {
+    match value {
+        None | Some("fail") => Ok(FileLock::Fail),
+        Some("wait") => Ok(FileLock::Wait),
+        Some("suffix") => Ok(FileLock::Suffix),
+        Some("none") => Ok(FileLock::Disabled),
+        Some(value) => Err(invalid_value(key, value, "fail, wait, suffix, or none")),
+    }
+}
+
+fn parse_fsync(key: &str, value: Option<&str>) -> Result<FsyncPolicy, ParseError> {
+    match value {
+        None | Some("always") => Ok(FsyncPolicy::Always),
+        Some("errors") => Ok(FsyncPolicy::Errors),
+        Some("never") => Ok(FsyncPolicy::Never),
+        Some(value) => humantime::parse_duration(value)
+            .ok()
+            .filter(|interval| *interval > Duration::ZERO)
+            .map(FsyncPolicy::Periodic)
+            .ok_or_else(|| {
+                invalid_value(key, value, "always, errors, never, or an interval like 5s")
+            }),
+    }
+}
+
+/// Parse a size like `1024`, `10MB` or `1GiB` into bytes.
+fn parse_size(key: &str, value: Option<&str>) -> Result<u64, ParseError> {
+    let value = required_value(key, value)?;
+    let digits = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(digits);
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1000,
+        "kib" => 1 << 10,
+        "m" | "mb" => 1000 * 1000,
+        "mib" => 1 << 20,
+        "g" | "gb" => 1000 * 1000 * 1000,
+        "gib" => 1 << 30,
+        "t" | "tb" => 1000 * 1000 * 1000 * 1000,
+        "tib" => 1 << 40,
+        _ => 0,
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .filter(|_| multiplier != 0)
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| invalid_value(key, value, "a size like 500MB or 1GiB"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileOptions;
+    use rstest::rstest;
+
+    /// Apply options to a destination, ignoring the level and filters of the config.
+    fn apply_destination_options(
+        destination: &mut LogDestination,
+        options: &str,
+    ) -> Result<(), ParseError> {
+        let mut config = LogDestinationConfig {
+            destination: destination.clone(),
+            level: None,
+            most_severe: None,
+            filters: Vec::new(),
+        };
+        apply_options(&mut config, options)?;
+        *destination = config.destination;
+        Ok(())
+    }
+
+    fn file_options(options: &str) -> Result<FileOptions, String> {
+        let mut destination = LogDestination::file("/path/to/file");
+        apply_destination_options(&mut destination, options).map_err(|err| err.to_string())?;
+        match destination {
+            LogDestination::File { options, .. } => Ok(options),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn file_options_all() {
+        assert_eq!(
+            FileOptions {
+                format: LogFormat::Json,
+                create_dirs: true,
+                mode: Some(0o640),
+                truncate: true,
+                lock: FileLock::Suffix,
+                fsync: FsyncPolicy::Periodic(Duration::from_secs(5)),
+                min_free_space: Some(1 << 30),
+                max_size: Some(10_000_000),
+            },
+            file_options(
+                "format=json,create_dirs,mode=640,truncate=true,lock=suffix,fsync=5s,min_free=1GiB,max_size=10MB"
+            )
+            .unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case("append=false", FileOptions { truncate: true, ..FileOptions::default() })]
+    #[case("append", FileOptions::default())]
+    #[case("create_dirs=false", FileOptions::default())]
+    #[case("lock", FileOptions { lock: FileLock::Fail, ..FileOptions::default() })]
+    #[case("lock=none", FileOptions::default())]
+    #[case("fsync", FileOptions { fsync: FsyncPolicy::Always, ..FileOptions::default() })]
+    #[case("fsync=errors", FileOptions { fsync: FsyncPolicy::Errors, ..FileOptions::default() })]
+    #[case(" format = json ", FileOptions { format: LogFormat::Json, ..FileOptions::default() })]
+    #[case("max_size=1024", FileOptions { max_size: Some(1024), ..FileOptions::default() })]
+    #[case("max_size=2k", FileOptions { max_size: Some(2000), ..FileOptions::default() })]
+    fn file_options_single(#[case] options: &str, #[case] expected: FileOptions) {
+        assert_eq!(expected, file_options(options).unwrap());
+    }
+
+    #[rstest]
+    #[case(
+        "rotate=10MB",
+        "Unknown option `rotate` for the file log destination. Choose format, create_dirs, mode, truncate, append, lock, fsync, min_free, max_size, include, exclude, include_target, exclude_target"
+    )]
+    #[case(
+        "format=xml",
+        "Invalid value `xml` for log destination option `format`. Expected text or json"
+    )]
+    #[case(
+        "format",
+        "Log destination option `format` requires a value, e.g. `format=...`"
+    )]
+    #[case(
+        "truncate=yes",
+        "Invalid value `yes` for log destination option `truncate`. Expected true or false"
+    )]
+    #[case(
+        "mode=999",
+        "Invalid value `999` for log destination option `mode`. Expected an octal file mode like 640"
+    )]
+    #[case(
+        "fsync=0s",
+        "Invalid value `0s` for log destination option `fsync`. Expected always, errors, never, or an interval like 5s"
+    )]
+    #[case(
+        "max_size=10XB",
+        "Invalid value `10XB` for log destination option `max_size`. Expected a size like 500MB or 1GiB"
+    )]
+    #[case(
+        "max_size=99999999TB",
+        "Invalid value `99999999TB` for log destination option `max_size`. Expected a size like 500MB or 1GiB"
+    )]
+    #[case(
+        "truncate,truncate=false",
+        "Log destination option `truncate` is given multiple times"
+    )]
+    #[case(
+        "",
+        "Invalid log destination option ``. Options are written as `key=value`, separated by commas, e.g. `file:/path/to/file;format=json,truncate`"
+    )]
+    #[case(
+        "truncate,,mode=600",
+        "Invalid log destination option ``. Options are written as `key=value`, separated by commas, e.g. `file:/path/to/file;format=json,truncate`"
+    )]
+    fn file_option_errors(#[case] options: &str, #[case] expected: &str) {
+        assert_eq!(expected, file_options(options).unwrap_err());
+    }
+
+    #[test]
+    fn network_options() {
+        let mut destination = LogDestination::Udp {
+            address: "localhost:9000".to_string(),
+            format: LogFormat::Text,
+        };
+        apply_destination_options(&mut destination, "format=json").unwrap();
+        assert_eq!(
+            LogDestination::Udp {
+                address: "localhost:9000".to_string(),
+                format: LogFormat::Json,
+            },
+            destination
+        );
+        assert_eq!(
+            "Unknown option `compress` for the udp log destination. Choose format, include, exclude, include_target, exclude_target",
+            apply_destination_options(&mut destination, "compress")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn gelf_options() {
+        let mut destination = LogDestination::Gelf {
+            address: "localhost:12201".to_string(),
+            transport: GelfTransport::Udp { compress: false },
+        };
+        apply_destination_options(&mut destination, "compress").unwrap();
+        assert_eq!(
+            LogDestination::Gelf {
+                address: "localhost:12201".to_string(),
+                transport: GelfTransport::Udp { compress: true },
+            },
+            destination
+        );
+        let mut destination = LogDestination::Gelf {
+            address: "localhost:12201".to_string(),
+            transport: GelfTransport::Tcp,
+        };
+        assert_eq!(
+            "Unknown option `compress` for the gelf+tcp log destination. Choose include, exclude, include_target, exclude_target",
+            apply_destination_options(&mut destination, "compress")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn otlp_options() {
+        let mut destination = LogDestination::Otlp {
+            endpoint: "http://localhost:4318".to_string(),
+            encoding: OtlpEncoding::Protobuf,
+        };
+        apply_destination_options(&mut destination, "encoding=json").unwrap();
+        assert_eq!(
+            LogDestination::Otlp {
+                endpoint: "http://localhost:4318".to_string(),
+                encoding: OtlpEncoding::Json,
+            },
+            destination
+        );
+    }
+
+    #[test]
+    fn ring_buffer_options_apply_to_sink() {
+        let mut destination = LogDestination::RingBuffer {
+            capacity: 10,
+            sink: Box::new(LogDestination::file("/path/to/file")),
+        };
+        apply_destination_options(&mut destination, "truncate,mode=600").unwrap();
+        assert_eq!(
+            LogDestination::RingBuffer {
+                capacity: 10,
+                sink: Box::new(LogDestination::File {
+                    path: "/path/to/file".into(),
+                    options: FileOptions {
+                        truncate: true,
+                        mode: Some(0o600),
+                        ..FileOptions::default()
+                    },
+                }),
+            },
+            destination
+        );
+    }
+
+    #[rstest]
+    #[case(LogDestination::Stderr, "stderr")]
+    #[case(LogDestination::Syslog, "syslog")]
+    #[case(LogDestination::Fd(3), "fd")]
+    #[case(LogDestination::Pipe("cat".to_string()), "pipe")]
+    #[case(LogDestination::Memory("name".to_string()), "memory")]
+    fn unsupported_options(#[case] mut destination: LogDestination, #[case] name: &str) {
+        assert_eq!(
+            format!(
+                "Unknown option `format` for the {name} log destination. Choose include, exclude, include_target, exclude_target"
+            ),
+            apply_destination_options(&mut destination, "format=json")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
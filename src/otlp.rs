@@ -0,0 +1,614 @@
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, SyncSender};
+use std::time::{Duration, Instant, SystemTime};
+
+use serde_json::Value;
+
+use super::config::OtlpEncoding;
+use super::fern::JsonFieldCollector;
+use super::utils::{collect_key_values, ignore_log_error, send_until};
+
+/// Export a batch once it has this many records, ...
+const MAX_BATCH_SIZE: usize = 512;
+/// ... or once its oldest record is this old.
+const BATCH_TIMEOUT: Duration = Duration::from_secs(1);
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Records queued for export before further records are dropped, e.g. while the collector is down
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// A [log::Log] implementation that exports records as OTLP log records to an OpenTelemetry collector.
+///
+/// Records are batched and exported over HTTP on a background thread. If the collector can't keep up, records are dropped.
+/// Flushing the logger exports all pending records and waits until that's done, e.g. when the [LoggingGuard](crate::LoggingGuard)
+/// is dropped before the process exits.
+/// Like for [GelfLogger](super::gelf::GelfLogger), records must not be formatted before.
+pub struct OtlpLogger {
+    sender: SyncSender<Message>,
+}
+
+enum Message {
+    Record(OtlpRecord),
+    Flush(Sender<()>),
+}
+
+impl OtlpLogger {
+    pub fn new(endpoint: &str, encoding: OtlpEncoding, service_name: String) -> Self {
+        let exporter = Exporter {
+            endpoint: logs_endpoint(endpoint),
+            encoding,
+            service_name,
+        };
+        let (sender, receiver) = std::sync::mpsc::sync_channel(QUEUE_CAPACITY);
+        std::thread::spawn(move || exporter.run(receiver));
+        Self { sender }
+    }
+}
+
+impl log::Log for OtlpLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        // Records are dropped while the queue is full
+        ignore_log_error(
+            self.sender
+                .try_send(Message::Record(OtlpRecord::from_record(record))),
+        );
+    }
+
+    fn flush(&self) {
+        let deadline = Instant::now() + EXPORT_TIMEOUT;
+        let (done_sender, done_receiver) = std::sync::mpsc::channel();
+        // If the queue is full, wait for the exporter to make room instead of giving up right away
+        if send_until(&self.sender, Message::Flush(done_sender), deadline).is_ok() {
+            let _ = done_receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()));
+        }
+    }
+}
+
+/// The OTLP/HTTP endpoint for logs. If only a base URL like `http://localhost:4318` is given, the default path is added.
+fn logs_endpoint(endpoint: &str) -> String {
+    let without_scheme = endpoint
+        .split_once("://")
+        .map_or(endpoint, |(_, rest)| rest);
+    if without_scheme.trim_end_matches('/').contains('/') {
+        endpoint.to_string()
+    } else {
+        format!("{}/v1/logs", endpoint.trim_end_matches('/'))
+    }
+}
+
+/// An owned copy of a log record, so it can be sent to the exporter thread.
+#[derive(Debug, Clone, PartialEq)]
+struct OtlpRecord {
+    time_unix_nano: u64,
+    level: log::Level,
+    body: String,
+    attributes: Vec<(String, Value)>,
+}
+
+impl OtlpRecord {
+    fn from_record(record: &log::Record) -> Self {
+        let mut attributes = vec![("target".to_string(), record.target().into())];
+        if let Some(file) = record.file() {
+            attributes.push(("code.filepath".to_string(), file.into()));
+        }
+        if let Some(line) = record.line() {
+            attributes.push(("code.lineno".to_string(), line.into()));
+        }
+        let JsonFieldCollector(fields) = collect_key_values(record);
+        attributes.extend(fields);
+        Self {
+            time_unix_nano: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64,
+            level: record.level(),
+            body: record.args().to_string(),
+            attributes,
+        }
+    }
+}
+
+/// The OTLP severity number of a log level
+fn severity_number(level: log::Level) -> u64 {
+    match level {
+        log::Level::Trace => 1,
+        log::Level::Debug => 5,
+        log::Level::Info => 9,
+        log::Level::Warn => 13,
+        log::Level::Error => 17,
+    }
+}
+
+struct Exporter {
+    endpoint: String,
+    encoding: OtlpEncoding,
+    service_name: String,
+}
+
+impl Exporter {
+    fn run(self, receiver: Receiver<Message>) {
+        let mut batch = Vec::new();
+        let mut deadline: Option<Instant> = None;
+        loop {
+            let message = match deadline {
+                Some(deadline) => {
+                    receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match message {
+                Ok(Message::Record(record)) => {
+                    batch.push(record);
+                    deadline.get_or_insert_with(|| Instant::now() + BATCH_TIMEOUT);
+                    if batch.len() >= MAX_BATCH_SIZE {
+                        self.export(&mut batch);
+                        deadline = None;
+                    }
+                }
+                Ok(Message::Flush(done)) => {
+                    self.export(&mut batch);
+                    deadline = None;
+                    let _ = done.send(());
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    self.export(&mut batch);
+                    deadline = None;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.export(&mut batch);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn export(&self, batch: &mut Vec<OtlpRecord>) {
+        if batch.is_empty() {
+            return;
+        }
+        let records = std::mem::take(batch);
+        let request = ureq::post(&self.endpoint).timeout(EXPORT_TIMEOUT);
+        ignore_log_error(match self.encoding {
+            OtlpEncoding::Protobuf => request
+                .set("Content-Type", "application/x-protobuf")
+                .send_bytes(&encode_protobuf(&self.service_name, &records)),
+            OtlpEncoding::Json => request
+                .set("Content-Type", "application/json")
+                .send_string(&encode_json(&self.service_name, &records).to_string()),
+        });
+    }
+}
+
+/// Encode an `ExportLogsServiceRequest` as OTLP/JSON.
+fn encode_json(service_name: &str, records: &[OtlpRecord]) -> Value {
+    let log_records: Vec<Value> = records
+        .iter()
+        .map(|record| {
+            serde_json::json!({
+                // 64 bit integers are encoded as strings in OTLP/JSON
+                "timeUnixNano": record.time_unix_nano.to_string(),
+                "observedTimeUnixNano": record.time_unix_nano.to_string(),
+                "severityNumber": severity_number(record.level),
+                "severityText": record.level.as_str(),
+                "body": {"stringValue": record.body},
+                "attributes": record.attributes.iter().map(|(key, value)| json_key_value(key, value)).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "resourceLogs": [{
+            "resource": {
+                "attributes": [json_key_value("service.name", &service_name.into())],
+            },
+            "scopeLogs": [{
+                "scope": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "logRecords": log_records,
+            }],
+        }],
+    })
+}
+
+fn json_key_value(key: &str, value: &Value) -> Value {
+    let value = match value {
+        Value::Bool(value) => serde_json::json!({"boolValue": value}),
+        Value::Number(number) if number.is_f64() => serde_json::json!({"doubleValue": number}),
+        Value::Number(number) => serde_json::json!({"intValue": number.to_string()}),
+        Value::String(value) => serde_json::json!({"stringValue": value}),
+        value => serde_json::json!({"stringValue": value.to_string()}),
+    };
+    serde_json::json!({"key": key, "value": value})
+}
+
+/// Encode an `ExportLogsServiceRequest` as OTLP/protobuf.
+///
+/// The few messages we need are encoded by hand instead of pulling in a protobuf code generator.
+/// Field numbers are from `opentelemetry/proto/collector/logs/v1/logs_service.proto` and its imports.
+fn encode_protobuf(service_name: &str, records: &[OtlpRecord]) -> Vec<u8> {
+    let mut resource = Vec::new();
+    // Resource.attributes
+    protobuf::message(
+        &mut resource,
+        1,
+        &protobuf_key_value("service.name", &service_name.into()),
+    );
+
+    let mut scope = Vec::new();
+    // InstrumentationScope.name and .version
+    protobuf::string(&mut scope, 1, env!("CARGO_PKG_NAME"));
+    protobuf::string(&mut scope, 2, env!("CARGO_PKG_VERSION"));
+
+    let mut scope_logs = Vec::new();
+    // ScopeLogs.scope
+    protobuf::message(&mut scope_logs, 1, &scope);
+    for record in records {
+        // ScopeLogs.log_records
+        protobuf::message(&mut scope_logs, 2, &protobuf_log_record(record));
+    }
+
+    let mut resource_logs = Vec::new();
+    // ResourceLogs.resource and .scope_logs
+    protobuf::message(&mut resource_logs, 1, &resource);
+    protobuf::message(&mut resource_logs, 2, &scope_logs);
+
+    let mut request = Vec::new();
+    // ExportLogsServiceRequest.resource_logs
+    protobuf::message(&mut request, 1, &resource_logs);
+    request
+}
+
+fn protobuf_log_record(record: &OtlpRecord) -> Vec<u8> {
+    let mut log_record = Vec::new();
+    protobuf::fixed64(&mut log_record, 1, record.time_unix_nano);
+    protobuf::varint_field(&mut log_record, 2, severity_number(record.level));
+    protobuf::string(&mut log_record, 3, record.level.as_str());
+    protobuf::message(
+        &mut log_record,
+        5,
+        &protobuf_any_value(&record.body.as_str().into()),
+    );
+    for (key, value) in &record.attributes {
+        protobuf::message(&mut log_record, 6, &protobuf_key_value(key, value));
+    }
+    protobuf::fixed64(&mut log_record, 11, record.time_unix_nano);
+    log_record
+}
+
+fn protobuf_key_value(key: &str, value: &Value) -> Vec<u8> {
+    let mut key_value = Vec::new();
+    protobuf::string(&mut key_value, 1, key);
+    protobuf::message(&mut key_value, 2, &protobuf_any_value(value));
+    key_value
+}
+
+fn protobuf_any_value(value: &Value) -> Vec<u8> {
+    let mut any_value = Vec::new();
+    match value {
+        Value::Bool(value) => protobuf::varint_field(&mut any_value, 2, u64::from(*value)),
+        Value::Number(number) => {
+            if let Some(value) = number.as_i64() {
+                protobuf::varint_field(&mut any_value, 3, value as u64);
+            } else if let Some(value) = number.as_f64() {
+                protobuf::fixed64(&mut any_value, 4, value.to_bits());
+            }
+        }
+        Value::String(value) => protobuf::string(&mut any_value, 1, value),
+        value => protobuf::string(&mut any_value, 1, &value.to_string()),
+    }
+    any_value
+}
+
+/// Minimal protobuf wire format encoding
+mod protobuf {
+    const WIRE_TYPE_VARINT: u64 = 0;
+    const WIRE_TYPE_FIXED64: u64 = 1;
+    const WIRE_TYPE_LEN: u64 = 2;
+
+    pub fn varint(buffer: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            buffer.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        buffer.push(value as u8);
+    }
+
+    fn tag(buffer: &mut Vec<u8>, field: u64, wire_type: u64) {
+        varint(buffer, (field << 3) | wire_type);
+    }
+
+    pub fn varint_field(buffer: &mut Vec<u8>, field: u64, value: u64) {
+        tag(buffer, field, WIRE_TYPE_VARINT);
+        varint(buffer, value);
+    }
+
+    pub fn fixed64(buffer: &mut Vec<u8>, field: u64, value: u64) {
+        tag(buffer, field, WIRE_TYPE_FIXED64);
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn bytes(buffer: &mut Vec<u8>, field: u64, value: &[u8]) {
+        tag(buffer, field, WIRE_TYPE_LEN);
+        varint(buffer, value.len() as u64);
+        buffer.extend_from_slice(value);
+    }
+
+    pub fn string(buffer: &mut Vec<u8>, field: u64, value: &str) {
+        bytes(buffer, field, value.as_bytes());
+    }
+
+    pub fn message(buffer: &mut Vec<u8>, field: u64, message: &[u8]) {
+        bytes(buffer, field, message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Log as _;
+    use rstest::rstest;
+    use std::io::{BufRead as _, BufReader, Read as _, Write as _};
+    use std::net::TcpListener;
+
+    fn record() -> OtlpRecord {
+        OtlpRecord {
+            time_unix_nano: 1_700_000_000_123_456_789,
+            level: log::Level::Warn,
+            body: "test log message".to_string(),
+            attributes: vec![
+                ("target".to_string(), "my-test".into()),
+                ("attempt".to_string(), 3.into()),
+                ("ratio".to_string(), 0.5.into()),
+                ("ok".to_string(), true.into()),
+            ],
+        }
+    }
+
+    /// A mock collector that accepts HTTP requests and returns their paths, content types and bodies.
+    fn mock_collector() -> (String, Receiver<(String, String, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line.split(' ').nth(1).unwrap().to_string();
+                let mut content_length = 0;
+                let mut content_type = String::new();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    let header = header.trim_end();
+                    if header.is_empty() {
+                        break;
+                    }
+                    let (name, value) = header.split_once(": ").unwrap();
+                    match name.to_ascii_lowercase().as_str() {
+                        "content-length" => content_length = value.parse().unwrap(),
+                        "content-type" => content_type = value.to_string(),
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .unwrap();
+                if sender.send((path, content_type, body)).is_err() {
+                    return;
+                }
+            }
+        });
+        (endpoint, receiver)
+    }
+
+    fn log(logger: &OtlpLogger, message: &str) {
+        let key_values = [("user", "alice")];
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("{message}"))
+                .level(log::Level::Info)
+                .target("my-test")
+                .file(Some("src/main.rs"))
+                .line(Some(12))
+                .key_values(&key_values)
+                .build(),
+        );
+    }
+
+    #[rstest]
+    #[case("http://localhost:4318", "http://localhost:4318/v1/logs")]
+    #[case("http://localhost:4318/", "http://localhost:4318/v1/logs")]
+    #[case("https://collector/custom/path", "https://collector/custom/path")]
+    #[case("http://localhost:4318/v1/logs", "http://localhost:4318/v1/logs")]
+    fn endpoint_path(#[case] endpoint: &str, #[case] expected: &str) {
+        assert_eq!(expected, logs_endpoint(endpoint));
+    }
+
+    #[test]
+    fn record_from_log_record() {
+        let key_values = [("user", "alice")];
+        let record = OtlpRecord::from_record(
+            &log::Record::builder()
+                .args(format_args!("test log message"))
+                .level(log::Level::Error)
+                .target("my-test")
+                .file(Some("src/main.rs"))
+                .line(Some(12))
+                .key_values(&key_values)
+                .build(),
+        );
+        assert_eq!(log::Level::Error, record.level);
+        assert_eq!("test log message", record.body);
+        assert_eq!(
+            vec![
+                ("target".to_string(), Value::from("my-test")),
+                ("code.filepath".to_string(), "src/main.rs".into()),
+                ("code.lineno".to_string(), 12.into()),
+                ("user".to_string(), "alice".into()),
+            ],
+            record.attributes
+        );
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            serde_json::json!({
+                "resourceLogs": [{
+                    "resource": {
+                        "attributes": [{"key": "service.name", "value": {"stringValue": "my-service"}}],
+                    },
+                    "scopeLogs": [{
+                        "scope": {"name": "clap-logflag", "version": env!("CARGO_PKG_VERSION")},
+                        "logRecords": [{
+                            "timeUnixNano": "1700000000123456789",
+                            "observedTimeUnixNano": "1700000000123456789",
+                            "severityNumber": 13,
+                            "severityText": "WARN",
+                            "body": {"stringValue": "test log message"},
+                            "attributes": [
+                                {"key": "target", "value": {"stringValue": "my-test"}},
+                                {"key": "attempt", "value": {"intValue": "3"}},
+                                {"key": "ratio", "value": {"doubleValue": 0.5}},
+                                {"key": "ok", "value": {"boolValue": true}},
+                            ],
+                        }],
+                    }],
+                }],
+            }),
+            encode_json("my-service", &[record()])
+        );
+    }
+
+    #[test]
+    fn protobuf_varint() {
+        let mut buffer = Vec::new();
+        protobuf::varint(&mut buffer, 1);
+        protobuf::varint(&mut buffer, 300);
+        assert_eq!(vec![0x01, 0xac, 0x02], buffer);
+    }
+
+    #[test]
+    fn protobuf_any_values() {
+        assert_eq!(
+            vec![0x0a, 0x02, b'h', b'i'],
+            protobuf_any_value(&"hi".into())
+        );
+        assert_eq!(vec![0x10, 0x01], protobuf_any_value(&true.into()));
+        assert_eq!(vec![0x18, 0x03], protobuf_any_value(&3.into()));
+        let mut double = vec![0x21];
+        double.extend_from_slice(&0.5f64.to_bits().to_le_bytes());
+        assert_eq!(double, protobuf_any_value(&0.5.into()));
+    }
+
+    #[test]
+    fn protobuf_request() {
+        let request = encode_protobuf("my-service", &[record()]);
+        let resource_logs = field(&request, 1);
+        let resource = field(resource_logs, 1);
+        let service_name = field(resource, 1);
+        assert_eq!(b"service.name", field(service_name, 1));
+        assert_eq!(b"my-service", field(field(service_name, 2), 1));
+        let scope_logs = field(resource_logs, 2);
+        assert_eq!(b"clap-logflag", field(field(scope_logs, 1), 1));
+        let log_record = field(scope_logs, 2);
+        assert_eq!(
+            1_700_000_000_123_456_789u64.to_le_bytes(),
+            field(log_record, 1)
+        );
+        assert_eq!(b"WARN", field(log_record, 3));
+        assert_eq!(b"test log message", field(field(log_record, 5), 1));
+    }
+
+    /// Find the first field with the given number in an encoded protobuf message and return its raw value.
+    fn field(message: &[u8], number: u64) -> &[u8] {
+        fn read_varint(message: &[u8], position: &mut usize) -> u64 {
+            let mut value = 0;
+            let mut shift = 0;
+            loop {
+                let byte = message[*position];
+                *position += 1;
+                value |= u64::from(byte & 0x7f) << shift;
+                if byte & 0x80 == 0 {
+                    return value;
+                }
+                shift += 7;
+            }
+        }
+
+        let mut position = 0;
+        while position < message.len() {
+            let tag = read_varint(message, &mut position);
+            let start = position;
+            match tag & 0x7 {
+                0 => {
+                    read_varint(message, &mut position);
+                }
+                1 => position += 8,
+                2 => {
+                    let len = read_varint(message, &mut position) as usize;
+                    if tag >> 3 == number {
+                        return &message[position..position + len];
+                    }
+                    position += len;
+                }
+                wire_type => panic!("Unexpected wire type {wire_type}"),
+            }
+            if tag >> 3 == number {
+                return &message[start..position];
+            }
+        }
+        panic!("Field {number} not found");
+    }
+
+    #[rstest]
+    fn export_to_collector(
+        #[values(OtlpEncoding::Protobuf, OtlpEncoding::Json)] encoding: OtlpEncoding,
+    ) {
+        let (endpoint, requests) = mock_collector();
+        let logger = OtlpLogger::new(&endpoint, encoding, "my-service".to_string());
+        log(&logger, "first message");
+        log(&logger, "second message");
+        logger.flush();
+
+        // Both records are exported in one batch
+        let (path, content_type, body) = requests.recv_timeout(EXPORT_TIMEOUT).unwrap();
+        assert_eq!("/v1/logs", path);
+        match encoding {
+            OtlpEncoding::Protobuf => {
+                assert_eq!("application/x-protobuf", content_type);
+                let scope_logs = field(field(&body, 1), 2);
+                assert_eq!(b"first message", field(field(field(scope_logs, 2), 5), 1));
+            }
+            OtlpEncoding::Json => {
+                assert_eq!("application/json", content_type);
+                let request: Value = serde_json::from_slice(&body).unwrap();
+                let log_records = &request["resourceLogs"][0]["scopeLogs"][0]["logRecords"];
+                assert_eq!(2, log_records.as_array().unwrap().len());
+                assert_eq!("first message", log_records[0]["body"]["stringValue"]);
+                assert_eq!("second message", log_records[1]["body"]["stringValue"]);
+            }
+        }
+    }
+
+    #[test]
+    fn export_after_batch_timeout() {
+        let (endpoint, requests) = mock_collector();
+        let logger = OtlpLogger::new(&endpoint, OtlpEncoding::Json, "my-service".to_string());
+        log(&logger, "message");
+        // No flush, the batch gets exported once it's old enough
+        let (_, _, body) = requests.recv_timeout(BATCH_TIMEOUT * 5).unwrap();
+        let request: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            "message",
+            request["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0]["body"]["stringValue"]
+        );
+    }
+}
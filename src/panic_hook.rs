@@ -0,0 +1,71 @@
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+
+use super::config::PanicHook;
+
+/// Install a panic hook that logs panics at ERROR level and then delegates to the previously installed panic hook.
+pub fn install(panic_hook: PanicHook) {
+    let capture_backtrace = match panic_hook {
+        PanicHook::Disabled => return,
+        PanicHook::Enabled => false,
+        PanicHook::EnabledWithBacktrace => true,
+    };
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let backtrace = capture_backtrace.then(Backtrace::force_capture);
+        log::error!(target: "panic", "{}", panic_log_message(info, backtrace.as_ref()));
+        // Make sure the message is written before the process potentially aborts
+        log::logger().flush();
+        previous_hook(info);
+    }));
+}
+
+fn panic_log_message(info: &PanicHookInfo, backtrace: Option<&Backtrace>) -> String {
+    let thread = std::thread::current();
+    let thread_name = thread.name().unwrap_or("<unnamed>");
+    let location = info
+        .location()
+        .map(|location| format!(" at {location}"))
+        .unwrap_or_default();
+    let payload = payload_as_str(info.payload());
+    let mut message = format!("thread '{thread_name}' panicked{location}:\n{payload}");
+    if let Some(backtrace) = backtrace {
+        message.push_str(&format!("\nstack backtrace:\n{backtrace}"));
+    }
+    message
+}
+
+/// Panics created with `panic!` have either a `&'static str` or a `String` payload.
+fn payload_as_str(payload: &(dyn Any + Send)) -> &str {
+    if let Some(payload) = payload.downcast_ref::<&'static str>() {
+        payload
+    } else if let Some(payload) = payload.downcast_ref::<String>() {
+        payload
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_static_str() {
+        let payload: Box<dyn Any + Send> = Box::new("static message");
+        assert_eq!("static message", payload_as_str(payload.as_ref()));
+    }
+
+    #[test]
+    fn payload_string() {
+        let payload: Box<dyn Any + Send> = Box::new(String::from("formatted message"));
+        assert_eq!("formatted message", payload_as_str(payload.as_ref()));
+    }
+
+    #[test]
+    fn payload_other() {
+        let payload: Box<dyn Any + Send> = Box::new(5);
+        assert_eq!("Box<dyn Any>", payload_as_str(payload.as_ref()));
+    }
+}
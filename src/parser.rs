@@ -1,7 +1,20 @@
-use log::LevelFilter;
+use log::{Level, LevelFilter};
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
 
-use super::config::{LogDestination, LogDestinationConfig};
+use super::config::{
+    FileLock, FileOptions, FsyncPolicy, GelfTransport, LogDestination, LogDestinationConfig,
+    LogFilter, LogFilterField, LogFilterKind, LogFilterPattern, LogFormat, LoggingConfig,
+    OtlpEncoding,
+};
+use super::options::apply_options;
+use super::sink::{LogSink, get_sink, registered_schemes};
+use super::suggestions::closest;
+use super::tokenizer::{
+    has_inner_quotes, quote, quote_allowing_colons, split_once_unquoted, split_unquoted,
+    split_unquoted_whitespace, unquote,
+};
 
 const LEVEL_ERROR: &str = "error";
 const LEVEL_ERROR_UPPER: &str = "ERROR";
@@ -13,19 +26,153 @@ const LEVEL_DEBUG: &str = "debug";
 const LEVEL_DEBUG_UPPER: &str = "DEBUG";
 const LEVEL_TRACE: &str = "trace";
 const LEVEL_TRACE_UPPER: &str = "TRACE";
+const LEVEL_OFF: &str = "off";
+const LEVEL_OFF_UPPER: &str = "OFF";
 
 const DEST_STDERR: &str = "stderr";
 const DEST_SYSLOG: &str = "syslog";
 const DEST_FILE: &str = "file";
+const DEST_MEMORY: &str = "memory";
+const DEST_FD: &str = "fd";
+const DEST_PIPE: &str = "pipe";
+const DEST_RINGBUFFER: &str = "ringbuffer";
+const DEST_TCP: &str = "tcp";
+const DEST_TCP_JSON: &str = "tcp+json";
+const DEST_UDP: &str = "udp";
+const DEST_UDP_JSON: &str = "udp+json";
+const DEST_UNIX: &str = "unix";
+const DEST_UNIX_JSON: &str = "unix+json";
+const DEST_GELF_UDP: &str = "gelf+udp";
+const DEST_GELF_UDP_GZIP: &str = "gelf+udp+gzip";
+const DEST_GELF_TCP: &str = "gelf+tcp";
+const DEST_OTLP: &str = "otlp";
+const DEST_OTLP_JSON: &str = "otlp+json";
 const DEST_NONE: &str = "none";
 
+/// The list of valid log destinations, used in error messages.
+fn destination_choices() -> String {
+    format!(
+        "{DEST_STDERR}, {DEST_SYSLOG}, {DEST_FILE}, {DEST_FD}, {DEST_PIPE}, {DEST_MEMORY}, {DEST_RINGBUFFER}, {DEST_TCP}, {DEST_UDP}, {DEST_UNIX}, {DEST_GELF_UDP}, {DEST_GELF_TCP}, {DEST_OTLP}, or {DEST_NONE}"
+    )
+}
+
+const LEVELS: [&str; 6] = [
+    LEVEL_OFF_UPPER,
+    LEVEL_ERROR_UPPER,
+    LEVEL_WARN_UPPER,
+    LEVEL_INFO_UPPER,
+    LEVEL_DEBUG_UPPER,
+    LEVEL_TRACE_UPPER,
+];
+
+/// The aliases that are accepted for levels, see [Token::parse_builtin], and the level they stand for
+const LEVEL_ALIASES: [(&str, &str); 6] = [
+    ("warning", LEVEL_WARN_UPPER),
+    ("err", LEVEL_ERROR_UPPER),
+    ("fatal", LEVEL_ERROR_UPPER),
+    ("crit", LEVEL_ERROR_UPPER),
+    ("critical", LEVEL_ERROR_UPPER),
+    ("verbose", LEVEL_TRACE_UPPER),
+];
+
+const DESTINATIONS: [&str; 19] = [
+    DEST_STDERR,
+    DEST_SYSLOG,
+    DEST_FILE,
+    DEST_MEMORY,
+    DEST_FD,
+    DEST_PIPE,
+    DEST_RINGBUFFER,
+    DEST_TCP,
+    DEST_TCP_JSON,
+    DEST_UDP,
+    DEST_UDP_JSON,
+    DEST_UNIX,
+    DEST_UNIX_JSON,
+    DEST_GELF_UDP,
+    DEST_GELF_UDP_GZIP,
+    DEST_GELF_TCP,
+    DEST_OTLP,
+    DEST_OTLP_JSON,
+    DEST_NONE,
+];
+
+/// The level the user probably meant with an invalid level filter, for "did you mean" hints in error messages.
+fn suggest_level(input: &str) -> Option<&'static str> {
+    let synonym = match input.to_ascii_lowercase().as_str() {
+        "severe" | "emerg" | "alert" => Some(LEVEL_ERROR_UPPER),
+        "information" | "notice" => Some(LEVEL_INFO_UPPER),
+        "dbg" => Some(LEVEL_DEBUG_UPPER),
+        "all" => Some(LEVEL_TRACE_UPPER),
+        "none" | "disabled" => Some(LEVEL_OFF_UPPER),
+        _ => None,
+    };
+    if synonym.is_some() {
+        return synonym;
+    }
+    let aliases = LEVEL_ALIASES.iter().map(|(alias, _)| *alias);
+    let closest = closest(input, LEVELS.into_iter().chain(aliases))?;
+    // Suggest the level instead of the alias, so the hint matches the levels listed in the error message
+    Some(
+        LEVEL_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == closest)
+            .map_or(closest, |(_, level)| *level),
+    )
+}
+
+/// The destination the user probably meant with an invalid destination, for "did you mean" hints in error messages.
+fn suggest_destination(input: &str) -> Option<String> {
+    let synonym = match input.to_ascii_lowercase().as_str() {
+        "stdout" | "console" | "terminal" | "tty" => Some(DEST_STDERR),
+        "journal" | "journald" => Some(DEST_SYSLOG),
+        "socket" => Some(DEST_UNIX),
+        "graylog" => Some(DEST_GELF_UDP),
+        "otel" | "opentelemetry" => Some(DEST_OTLP),
+        "null" | "disabled" => Some(DEST_NONE),
+        _ => None,
+    };
+    if let Some(synonym) = synonym {
+        return Some(synonym.to_string());
+    }
+    let schemes = registered_schemes();
+    closest(
+        input,
+        DESTINATIONS
+            .into_iter()
+            .chain(schemes.iter().map(String::as_str)),
+    )
+    .map(str::to_string)
+}
+
+/// If the input is a level directly followed by a destination, e.g. `ERRORstderr`, return it with the missing colon.
+fn suggest_missing_colon(input: &str) -> Option<String> {
+    LEVELS.into_iter().find_map(|level| {
+        let prefix = input.get(..level.len())?;
+        let destination = &input[level.len()..];
+        let is_destination = matches!(Token::parse(destination), Some(Token::Destination(_)));
+        (prefix.eq_ignore_ascii_case(level) && is_destination)
+            .then(|| format!("{prefix}:{destination}"))
+    })
+}
+
+/// Format a suggestion as a sentence to add to an error message, or an empty string if there is none.
+fn did_you_mean(suggestion: Option<impl Display>) -> String {
+    suggestion
+        .map(|suggestion| format!(" Did you mean `{suggestion}`?"))
+        .unwrap_or_default()
+}
+
+/// An invalid log definition, e.g. when parsing a [LogDestinationConfig] or [LoggingConfig] from a string.
+///
+/// The message explains the problem to the user, like the error messages for invalid `--log` arguments.
 #[derive(Debug, Clone)]
 pub struct ParseError {
     message: String,
 }
 
 impl ParseError {
-    pub fn new(message: String) -> Self {
+    pub(crate) fn new(message: String) -> Self {
         Self { message }
     }
 }
@@ -36,12 +183,18 @@ impl Display for ParseError {
     }
 }
 
+impl std::error::Error for ParseError {}
+
 enum Token {
     Level(TokenLevel),
+    /// A level range like `DEBUG..INFO`, `..INFO`, or an exact level like `=WARN`
+    LevelRange(Option<TokenLevel>, TokenLevel),
     Destination(TokenDestination),
 }
 
+#[derive(Clone)]
 enum TokenLevel {
+    Off,
     Error,
     Warn,
     Info,
@@ -53,42 +206,184 @@ enum TokenDestination {
     Stderr,
     Syslog,
     File,
+    Memory,
+    Fd,
+    Pipe,
+    RingBuffer,
+    Tcp(LogFormat),
+    Udp(LogFormat),
+    Unix(LogFormat),
+    Gelf(GelfTransport),
+    Otlp(OtlpEncoding),
     None,
+    Custom(String, Arc<dyn LogSink>),
 }
 
 impl Token {
     fn parse(input: &str) -> Option<Self> {
+        Self::parse_builtin(input)
+            .or_else(|| Self::parse_level_range(input))
+            .or_else(|| {
+                let scheme = input.to_ascii_lowercase();
+                get_sink(&scheme)
+                    .map(|sink| Token::Destination(TokenDestination::Custom(scheme, sink)))
+            })
+    }
+
+    fn parse_level_range(input: &str) -> Option<Self> {
+        let parse_level = |input: &str| match Self::parse_builtin(input)? {
+            Token::Level(level) => Some(level),
+            _ => None,
+        };
+        if let Some(level) = input.strip_prefix('=') {
+            let level = parse_level(level)?;
+            return Some(Token::LevelRange(Some(level.clone()), level));
+        }
+        let (from, to) = input.split_once("..")?;
+        let from = if from.is_empty() {
+            None
+        } else {
+            Some(parse_level(from)?)
+        };
+        Some(Token::LevelRange(from, parse_level(to)?))
+    }
+
+    fn parse_builtin(input: &str) -> Option<Self> {
         match input.to_ascii_lowercase().as_str() {
-            LEVEL_ERROR => Some(Token::Level(TokenLevel::Error)),
-            LEVEL_WARN => Some(Token::Level(TokenLevel::Warn)),
-            LEVEL_INFO => Some(Token::Level(TokenLevel::Info)),
-            LEVEL_DEBUG => Some(Token::Level(TokenLevel::Debug)),
-            LEVEL_TRACE => Some(Token::Level(TokenLevel::Trace)),
+            // Aliases used by other logging ecosystems, and the numeric values of [LevelFilter]
+            LEVEL_OFF | "0" => Some(Token::Level(TokenLevel::Off)),
+            LEVEL_ERROR | "err" | "fatal" | "crit" | "critical" | "1" => {
+                Some(Token::Level(TokenLevel::Error))
+            }
+            LEVEL_WARN | "warning" | "2" => Some(Token::Level(TokenLevel::Warn)),
+            LEVEL_INFO | "3" => Some(Token::Level(TokenLevel::Info)),
+            LEVEL_DEBUG | "4" => Some(Token::Level(TokenLevel::Debug)),
+            LEVEL_TRACE | "verbose" | "5" => Some(Token::Level(TokenLevel::Trace)),
             DEST_STDERR => Some(Token::Destination(TokenDestination::Stderr)),
             DEST_SYSLOG => Some(Token::Destination(TokenDestination::Syslog)),
             DEST_FILE => Some(Token::Destination(TokenDestination::File)),
+            DEST_MEMORY => Some(Token::Destination(TokenDestination::Memory)),
+            DEST_FD => Some(Token::Destination(TokenDestination::Fd)),
+            DEST_PIPE => Some(Token::Destination(TokenDestination::Pipe)),
+            DEST_RINGBUFFER => Some(Token::Destination(TokenDestination::RingBuffer)),
+            DEST_TCP => Some(Token::Destination(TokenDestination::Tcp(LogFormat::Text))),
+            DEST_TCP_JSON => Some(Token::Destination(TokenDestination::Tcp(LogFormat::Json))),
+            DEST_UDP => Some(Token::Destination(TokenDestination::Udp(LogFormat::Text))),
+            DEST_UDP_JSON => Some(Token::Destination(TokenDestination::Udp(LogFormat::Json))),
+            DEST_UNIX => Some(Token::Destination(TokenDestination::Unix(LogFormat::Text))),
+            DEST_UNIX_JSON => Some(Token::Destination(TokenDestination::Unix(LogFormat::Json))),
+            DEST_GELF_UDP => Some(Token::Destination(TokenDestination::Gelf(
+                GelfTransport::Udp { compress: false },
+            ))),
+            DEST_GELF_UDP_GZIP => Some(Token::Destination(TokenDestination::Gelf(
+                GelfTransport::Udp { compress: true },
+            ))),
+            DEST_GELF_TCP => Some(Token::Destination(TokenDestination::Gelf(
+                GelfTransport::Tcp,
+            ))),
+            DEST_OTLP => Some(Token::Destination(TokenDestination::Otlp(
+                OtlpEncoding::Protobuf,
+            ))),
+            DEST_OTLP_JSON => Some(Token::Destination(TokenDestination::Otlp(
+                OtlpEncoding::Json,
+            ))),
             DEST_NONE => Some(Token::Destination(TokenDestination::None)),
             _ => None,
         }
     }
 }
 
+/// Return true if the input is a built-in log level or log destination.
+pub fn is_builtin_token(input: &str) -> bool {
+    Token::parse_builtin(input).is_some()
+}
+
 /// Parse a log definition consisting of an optional log level, and a log destination.
 ///
-/// Format: [level:]destination
-/// level = "ERROR" | "WARN" | "INFO" | "DEBUG" | "TRACE"
-/// destination = "stderr" | "syslog" | "file:path" | "none"
+/// Format: [level_filter:]destination[;options]
+/// level_filter = level | level..level | ..level | =level
+/// level = "OFF" | "ERROR" | "WARN" | "INFO" | "DEBUG" | "TRACE", or an alias like "warning", "fatal", "verbose", or "0" to "5"
+/// destination = "stderr" | "syslog" | "file:path" | "fd:number" | "pipe:command" | "memory:name" | "ringbuffer:capacity:sink" | "tcp://host:port" | "tcp+json://host:port" | "udp://host:port" | "udp+json://host:port" | "unix:path" | "unix+json:path" | "gelf+udp://host:port" | "gelf+udp+gzip://host:port" | "gelf+tcp://host:port" | "otlp:url" | "otlp+json:url" | "none" | custom_scheme[:argument]
 ///
 /// Examples:
 /// * "syslog"
 /// * "stderr"
 /// * "none"
 /// * "file:/path/to/file"
+/// * "memory:name"
+/// * "fd:3"
+/// * "pipe:logger -t myapp"
+/// * "TRACE:ringbuffer:1000:file:/path/to/file"
+/// * "INFO:tcp://localhost:9000"
+/// * "udp+json://[::1]:9000"
+/// * "unix:/run/log-shipper.sock"
+/// * "INFO:gelf+udp://graylog:12201"
+/// * "INFO:otlp:http://localhost:4318"
 /// * "INFO:stderr"
 /// * "DEBUG:file:/path/to/file"
 /// * "TRACE:syslog"
+/// * "DEBUG..INFO:file:/path/to/file"
+/// * "=WARN:stderr"
+/// * "INFO:myqueue:topic" (if a [LogSink] was registered for `myqueue`)
+/// * "INFO:file:/path/to/file;format=json,max_size=1GB"
+///
+/// Options after the first `;` are parsed by [apply_options]. Paths and commands containing `:` or `;` can be quoted,
+/// e.g. `file:"/path/with:colon"`, or escaped with a backslash, see [split_unquoted].
 pub fn parse_config_definition(input: &str) -> Result<Option<LogDestinationConfig>, ParseError> {
-    let parts: Vec<&str> = input.split(':').collect();
+    let (definition, options) = split_once_unquoted(input, ';')?;
+    let raw_parts = split_unquoted(definition, ':')?;
+    let parts: Vec<String> = raw_parts
+        .iter()
+        .map(|part| unquote(part).into_owned())
+        .collect();
+    let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+    check_pipe_command_quotes(&raw_parts, &parts)?;
+    let config = parse_parts(definition, &parts)?;
+    match (config, options) {
+        (Some(mut config), Some(options)) => {
+            apply_options(&mut config, options)?;
+            Ok(Some(config))
+        }
+        (None, Some(_)) => Err(ParseError::new(format!(
+            "The {DEST_NONE} log destination doesn't support options"
+        ))),
+        (config, None) => Ok(config),
+    }
+}
+
+/// Quotes in the middle of a pipe command would be removed before the shell sees them, e.g. `pipe:logger -t "my app"`
+/// would run `logger -t my app`. Reject them instead of silently changing what the command does.
+fn check_pipe_command_quotes(raw_parts: &[&str], parts: &[&str]) -> Result<(), ParseError> {
+    let is_pipe = |part: &str| {
+        matches!(
+            Token::parse(part),
+            Some(Token::Destination(TokenDestination::Pipe))
+        )
+    };
+    let is_level = |part: &str| {
+        matches!(
+            Token::parse(part),
+            Some(Token::Level(_) | Token::LevelRange(..))
+        )
+    };
+    let command_start = match parts {
+        [destination, ..] if is_pipe(destination) => 1,
+        [level, destination, ..] if is_level(level) && is_pipe(destination) => 2,
+        _ => return Ok(()),
+    };
+    let command = &raw_parts[command_start..];
+    if command.iter().any(|part| has_inner_quotes(part)) {
+        return Err(ParseError::new(format!(
+            "Quotes inside of the pipe command `{}` would be removed before the shell runs it. Escape them like `{DEST_PIPE}:logger -t \\\"my app\\\"`, or quote the whole command like `{DEST_PIPE}:\"logger -t \"\"my app\"\"\"`",
+            command.join(":")
+        )));
+    }
+    Ok(())
+}
+
+/// Parse the colon separated parts of a log definition without the options suffix, with quotes and escapes already removed.
+/// The input is only used in error messages.
+fn parse_parts(input: &str, parts: &[&str]) -> Result<Option<LogDestinationConfig>, ParseError> {
     assert!(
         !parts.is_empty(),
         "Splitting should always return at least one part"
@@ -102,27 +397,27 @@ pub fn parse_config_definition(input: &str) -> Result<Option<LogDestinationConfi
             parse_config_definition_without_level(destination, &parts[1..])
         }
         Some(Token::Level(level)) => {
-            // Otherwise, if the first component is a level, parse the second one as a destination and the rest as extras.
-            if parts.len() >= 2 {
-                parse_config_definition_with_level(level, parts[0], parts[1], &parts[2..])
-            } else {
-                // Seems we only have a level, no destination.
-                Err(ParseError::new(format!(
-                    "Expected log destination but found level filter `{}`. Please add a destination. Example: `--log {}:stderr`",
-                    parts[0], parts[0],
-                )))
-            }
+            parse_config_definition_with_level(Some(parse_level(level)?), None, parts)
+        }
+        Some(Token::LevelRange(from, to)) => {
+            let (level, most_severe) = parse_level_range(from, to)?;
+            parse_config_definition_with_level(level, Some(most_severe), parts)
         }
         None => {
             if parts.len() == 1 {
                 // We only have one part, no colons. Let's assume the user wanted to write a log destination.
                 if parts[0].is_empty() {
                     Err(ParseError::new(format!(
-                        "Invalid empty log destination. Choose {DEST_STDERR}, {DEST_SYSLOG}, {DEST_FILE}, or {DEST_NONE}"
+                        "Invalid empty log destination. Choose {}",
+                        destination_choices(),
                     )))
                 } else {
+                    let suggestion =
+                        suggest_missing_colon(input).or_else(|| suggest_destination(input));
                     Err(ParseError::new(format!(
-                        "Invalid log destination `{input}`. Choose {DEST_STDERR}, {DEST_SYSLOG}, {DEST_FILE}, or {DEST_NONE}"
+                        "Invalid log destination `{input}`.{} Choose {}",
+                        did_you_mean(suggestion),
+                        destination_choices(),
                     )))
                 }
             } else {
@@ -132,20 +427,38 @@ pub fn parse_config_definition(input: &str) -> Result<Option<LogDestinationConfi
                         // The second part is a destination, so let's assume the user wanted to write a level filter in the first part.
                         let error = if parts[0].is_empty() {
                             ParseError::new(format!(
-                                "Invalid empty log level filter. Choose {LEVEL_ERROR_UPPER}, {LEVEL_WARN_UPPER}, {LEVEL_INFO_UPPER}, {LEVEL_DEBUG_UPPER}, or {LEVEL_TRACE_UPPER}"
+                                "Invalid empty log level filter. Choose {LEVEL_OFF_UPPER}, {LEVEL_ERROR_UPPER}, {LEVEL_WARN_UPPER}, {LEVEL_INFO_UPPER}, {LEVEL_DEBUG_UPPER}, or {LEVEL_TRACE_UPPER}"
                             ))
                         } else {
                             ParseError::new(format!(
-                                "Invalid log level filter `{}`. Choose {LEVEL_ERROR_UPPER}, {LEVEL_WARN_UPPER}, {LEVEL_INFO_UPPER}, {LEVEL_DEBUG_UPPER}, or {LEVEL_TRACE_UPPER}",
-                                parts[0]
+                                "Invalid log level filter `{}`.{} Choose {LEVEL_OFF_UPPER}, {LEVEL_ERROR_UPPER}, {LEVEL_WARN_UPPER}, {LEVEL_INFO_UPPER}, {LEVEL_DEBUG_UPPER}, or {LEVEL_TRACE_UPPER}",
+                                parts[0],
+                                did_you_mean(suggest_level(parts[0])),
                             ))
                         };
                         Err(error)
                     }
                     _ => {
-                        // The second part is not a destination either. Let's just show a generic error message
+                        // The second part is not a destination either. Maybe both parts have typos, or the first part is a destination with a typo.
+                        let suggestion =
+                            match (suggest_level(parts[0]), suggest_destination(parts[1])) {
+                                (Some(level), Some(destination)) => Some(
+                                    [level, &destination]
+                                        .into_iter()
+                                        .chain(parts[2..].iter().copied())
+                                        .collect::<Vec<_>>()
+                                        .join(":"),
+                                ),
+                                _ => suggest_destination(parts[0]).map(|destination| {
+                                    std::iter::once(destination.as_str())
+                                        .chain(parts[1..].iter().copied())
+                                        .collect::<Vec<_>>()
+                                        .join(":")
+                                }),
+                            };
                         Err(ParseError::new(format!(
-                            "Invalid log configuration `{input}`. Examples: `{DEST_STDERR}`, `{LEVEL_ERROR_UPPER}:{DEST_SYSLOG}`, `{LEVEL_WARN_UPPER}:{DEST_FILE}:/path/to/file`",
+                            "Invalid log configuration `{input}`.{} Examples: `{DEST_STDERR}`, `{LEVEL_ERROR_UPPER}:{DEST_SYSLOG}`, `{LEVEL_WARN_UPPER}:{DEST_FILE}:/path/to/file`",
+                            did_you_mean(suggestion),
                         )))
                     }
                 }
@@ -162,25 +475,61 @@ fn parse_config_definition_without_level(
     Ok(destination.map(|destination| LogDestinationConfig {
         level: None,
         destination,
+        most_severe: None,
+        filters: Vec::new(),
     }))
 }
 
+/// Parse a log definition whose first part is a level filter or level range,
+/// the second one as a destination and the rest as extras.
 fn parse_config_definition_with_level(
-    level: TokenLevel,
-    level_str: &str,
-    destination: &str,
-    extras: &[&str],
+    level: Option<LevelFilter>,
+    most_severe: Option<Level>,
+    parts: &[&str],
 ) -> Result<Option<LogDestinationConfig>, ParseError> {
-    let level_filter = parse_level(level)?;
-    let destination = tokenize_and_parse_destination(level_str, destination, extras)?;
+    if parts.len() < 2 {
+        // Seems we only have a level, no destination.
+        return Err(ParseError::new(format!(
+            "Expected log destination but found level filter `{}`. Please add a destination. Example: `--log {}:stderr`",
+            parts[0], parts[0],
+        )));
+    }
+    let destination = tokenize_and_parse_destination(parts[0], parts[1], &parts[2..])?;
     Ok(destination.map(|destination| LogDestinationConfig {
-        level: Some(level_filter),
+        level,
         destination,
+        most_severe,
+        filters: Vec::new(),
     }))
 }
 
+/// Parse a level range like `DEBUG..INFO` into the most verbose and the most severe level that are logged.
+/// The bounds can be given in either order.
+fn parse_level_range(
+    from: Option<TokenLevel>,
+    to: TokenLevel,
+) -> Result<(Option<LevelFilter>, Level), ParseError> {
+    let from = from.map(parse_level).transpose()?;
+    let to = parse_level(to)?;
+    let (level, most_severe) = match from {
+        Some(from) if from < to => (Some(to), from),
+        Some(from) => (Some(from), to),
+        None => (None, to),
+    };
+    let off_error = || {
+        ParseError::new(format!(
+            "Level ranges can't include {LEVEL_OFF_UPPER}. Use `{LEVEL_OFF_UPPER}:destination` to disable a destination"
+        ))
+    };
+    if level == Some(LevelFilter::Off) {
+        return Err(off_error());
+    }
+    Ok((level, most_severe.to_level().ok_or_else(off_error)?))
+}
+
 fn parse_level(level: TokenLevel) -> Result<LevelFilter, ParseError> {
     match level {
+        TokenLevel::Off => Ok(LevelFilter::Off),
         TokenLevel::Error => Ok(LevelFilter::Error),
         TokenLevel::Warn => Ok(LevelFilter::Warn),
         TokenLevel::Info => Ok(LevelFilter::Info),
@@ -198,17 +547,20 @@ fn tokenize_and_parse_destination(
         Some(Token::Destination(destination)) => {
             parse_destination(Some(level), destination, extras)
         }
-        Some(Token::Level(_)) => Err(ParseError::new(format!(
+        Some(Token::Level(_) | Token::LevelRange(..)) => Err(ParseError::new(format!(
             "Expected log destination but found level filter `{destination}`. Please add a destination. Example: `--log {level}:stderr`"
         ))),
         None => {
             let error = if destination.is_empty() {
                 ParseError::new(format!(
-                    "Invalid empty log destination. Choose {DEST_STDERR}, {DEST_SYSLOG}, {DEST_FILE}, or {DEST_NONE}"
+                    "Invalid empty log destination. Choose {}",
+                    destination_choices(),
                 ))
             } else {
                 ParseError::new(format!(
-                    "Invalid log destination `{destination}`. Choose {DEST_STDERR}, {DEST_SYSLOG}, {DEST_FILE}, or {DEST_NONE}"
+                    "Invalid log destination `{destination}`.{} Choose {}",
+                    did_you_mean(suggest_destination(destination)),
+                    destination_choices(),
                 ))
             };
             Err(error)
@@ -240,12 +592,425 @@ fn parse_destination(
                     "File log destination requires a path. Example: `--log {level}{DEST_FILE}:/path/to/file`"
                 )));
             }
-            Some(LogDestination::File(path.into()))
+            Some(LogDestination::file(path))
+        }
+        TokenDestination::Memory => {
+            // An empty name is allowed as long as it's given explicitly, e.g. `memory:""`
+            if extras.is_empty() {
+                let level = level.map(|level| format!("{level}:")).unwrap_or_default();
+                return Err(ParseError::new(format!(
+                    "Memory log destination requires a name. Example: `--log {level}{DEST_MEMORY}:name`"
+                )));
+            }
+            // If we find multiple extras, then the name was split by a colon. Reconnect it.
+            Some(LogDestination::Memory(extras.join(":")))
+        }
+        TokenDestination::Fd => {
+            let fd = match extras {
+                [fd] => fd.parse::<u32>().ok(),
+                _ => None,
+            };
+            let Some(fd) = fd else {
+                let level = level.map(|level| format!("{level}:")).unwrap_or_default();
+                return Err(ParseError::new(format!(
+                    "File descriptor log destination requires a file descriptor number. Example: `--log {level}{DEST_FD}:3`"
+                )));
+            };
+            Some(LogDestination::Fd(fd))
+        }
+        TokenDestination::Pipe => {
+            // If we find multiple extras, then the command was split by a colon. Reconnect it.
+            let command = extras.join(":");
+            if command.trim().is_empty() {
+                let level = level.map(|level| format!("{level}:")).unwrap_or_default();
+                return Err(ParseError::new(format!(
+                    "Pipe log destination requires a command. Example: `--log \"{level}{DEST_PIPE}:logger -t myapp\"`"
+                )));
+            }
+            Some(LogDestination::Pipe(command))
+        }
+        TokenDestination::RingBuffer => {
+            let level = level.map(|level| format!("{level}:")).unwrap_or_default();
+            let example =
+                format!("Example: `--log {level}{DEST_RINGBUFFER}:1000:{DEST_FILE}:/path/to/file`");
+            if extras.len() < 2 {
+                return Err(ParseError::new(format!(
+                    "Ring buffer log destination requires a capacity and a sink. {example}"
+                )));
+            }
+            let capacity = extras[0]
+                .parse::<usize>()
+                .ok()
+                .filter(|capacity| *capacity > 0)
+                .ok_or_else(|| {
+                    ParseError::new(format!(
+                        "Invalid ring buffer capacity `{}`. The capacity must be a positive number. {example}",
+                        extras[0]
+                    ))
+                })?;
+            // The remaining parts define the sink, e.g. `stderr` or `file:/path/to/file`
+            let sink = parse_parts(&extras[1..].join(":"), &extras[1..])?;
+            match sink {
+                Some(LogDestinationConfig {
+                    level: None,
+                    destination: sink @ (LogDestination::Stderr | LogDestination::File { .. }),
+                    most_severe: None,
+                    ..
+                }) => Some(LogDestination::RingBuffer {
+                    capacity,
+                    sink: Box::new(sink),
+                }),
+                _ => {
+                    return Err(ParseError::new(format!(
+                        "Ring buffer sink must be {DEST_STDERR} or {DEST_FILE} without a level filter. {example}"
+                    )));
+                }
+            }
+        }
+        TokenDestination::Tcp(format) => {
+            let scheme = match format {
+                LogFormat::Text => DEST_TCP,
+                LogFormat::Json => DEST_TCP_JSON,
+            };
+            Some(LogDestination::Tcp {
+                address: parse_network_address("TCP", scheme, 9000, level, extras)?,
+                format,
+            })
+        }
+        TokenDestination::Udp(format) => {
+            let scheme = match format {
+                LogFormat::Text => DEST_UDP,
+                LogFormat::Json => DEST_UDP_JSON,
+            };
+            Some(LogDestination::Udp {
+                address: parse_network_address("UDP", scheme, 9000, level, extras)?,
+                format,
+            })
+        }
+        TokenDestination::Gelf(transport) => {
+            let scheme = match transport {
+                GelfTransport::Udp { compress: false } => DEST_GELF_UDP,
+                GelfTransport::Udp { compress: true } => DEST_GELF_UDP_GZIP,
+                GelfTransport::Tcp => DEST_GELF_TCP,
+            };
+            Some(LogDestination::Gelf {
+                address: parse_network_address("GELF", scheme, 12201, level, extras)?,
+                transport,
+            })
+        }
+        TokenDestination::Otlp(encoding) => {
+            // The URL was split at the colons, e.g. `http://localhost:4318` became `["http", "//localhost", "4318"]`. Reconnect it.
+            let endpoint = extras.join(":");
+            let has_host = ["http://", "https://"].iter().any(|scheme| {
+                endpoint
+                    .strip_prefix(scheme)
+                    .is_some_and(|rest| !rest.is_empty() && !rest.starts_with('/'))
+            });
+            if !has_host {
+                let level = level.map(|level| format!("{level}:")).unwrap_or_default();
+                let scheme = match encoding {
+                    OtlpEncoding::Protobuf => DEST_OTLP,
+                    OtlpEncoding::Json => DEST_OTLP_JSON,
+                };
+                return Err(ParseError::new(format!(
+                    "OTLP log destination requires an http:// or https:// endpoint. Example: `--log {level}{scheme}:http://localhost:4318`"
+                )));
+            }
+            Some(LogDestination::Otlp { endpoint, encoding })
+        }
+        TokenDestination::Unix(format) => {
+            // If we find multiple extras, then the socket path was split by a colon. Reconnect it.
+            let path = extras.join(":");
+            if path.is_empty() {
+                let level = level.map(|level| format!("{level}:")).unwrap_or_default();
+                let scheme = match format {
+                    LogFormat::Text => DEST_UNIX,
+                    LogFormat::Json => DEST_UNIX_JSON,
+                };
+                return Err(ParseError::new(format!(
+                    "Unix socket log destination requires a path. Example: `--log {level}{scheme}:/path/to/socket`"
+                )));
+            }
+            Some(LogDestination::Unix {
+                path: path.into(),
+                format,
+            })
+        }
+        TokenDestination::Custom(scheme, sink) => {
+            // If we find multiple extras, then the argument was split by a colon. Reconnect it.
+            let argument = extras.join(":");
+            sink.validate(&argument).map_err(ParseError::new)?;
+            Some(LogDestination::Custom { scheme, argument })
         }
     };
     Ok(destination)
 }
 
+/// Parse the `//host:port` part of a network log destination and return it as `host:port`.
+fn parse_network_address(
+    protocol: &str,
+    scheme: &str,
+    example_port: u16,
+    level: Option<&str>,
+    extras: &[&str],
+) -> Result<String, ParseError> {
+    // The address was split at the colons, e.g. `//localhost:9000` became `["//localhost", "9000"]`. Reconnect it.
+    let address = extras.join(":");
+    let valid_address = address
+        .strip_prefix("//")
+        .and_then(|address| address.rsplit_once(':'))
+        .filter(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok_and(|port| port != 0));
+    match valid_address {
+        Some((host, port)) => Ok(format!("{host}:{port}")),
+        None => {
+            let level = level.map(|level| format!("{level}:")).unwrap_or_default();
+            Err(ParseError::new(format!(
+                "{protocol} log destination requires an address with a host and a port. Example: `--log {level}{scheme}://localhost:{example_port}`"
+            )))
+        }
+    }
+}
+
+/// Parses a log definition with the same syntax as the `--log` argument, e.g. `INFO:file:/path/to/file`.
+///
+/// `none` can't be parsed as a [LogDestinationConfig], parse it as a [LoggingConfig] instead.
+impl FromStr for LogDestinationConfig {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        parse_config_definition(input)?.ok_or_else(|| {
+            ParseError::new(format!(
+                "`{DEST_NONE}` disables logging and isn't a log destination. Parse it as a LoggingConfig instead"
+            ))
+        })
+    }
+}
+
+/// Formats the log definition with the same syntax as the `--log` argument, so that parsing it returns the same [LogDestinationConfig].
+impl Display for LogDestinationConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.level, self.most_severe) {
+            (Some(level), Some(most_severe)) if level == most_severe.to_level_filter() => {
+                write!(f, "={level}:")?
+            }
+            (Some(level), Some(most_severe)) => write!(f, "{level}..{most_severe}:")?,
+            (None, Some(most_severe)) => write!(f, "..{most_severe}:")?,
+            (Some(level), None) => write!(f, "{level}:")?,
+            (None, None) => {}
+        }
+        write!(f, "{}", format_destination(&self.destination))?;
+        let mut options = format_options(&self.destination);
+        options.extend(self.filters.iter().map(format_filter));
+        if !options.is_empty() {
+            write!(f, ";{}", options.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats the destination with the same syntax as the `--log` argument, e.g. `file:/path/to/file;truncate`.
+///
+/// File paths that aren't valid UTF-8 are written lossily.
+impl Display for LogDestination {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format_destination(self))?;
+        let options = format_options(self);
+        if !options.is_empty() {
+            write!(f, ";{}", options.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses whitespace separated log definitions, e.g. `INFO:stderr DEBUG:file:/path/to/file`.
+/// Definitions containing whitespace need to be quoted, e.g. `pipe:"gzip > /path/to/file.gz"`.
+///
+/// `none` disables logging, unless it's combined with other log definitions.
+impl FromStr for LoggingConfig {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let definitions = split_unquoted_whitespace(input)?;
+        if definitions.is_empty() {
+            return Err(ParseError::new(format!(
+                "Invalid empty logging configuration. Use `{DEST_NONE}` to disable logging"
+            )));
+        }
+        let mut destinations = Vec::with_capacity(definitions.len());
+        for definition in definitions {
+            destinations.extend(parse_config_definition(definition)?);
+        }
+        Ok(LoggingConfig::new(destinations))
+    }
+}
+
+/// Formats the log definitions separated by spaces, or `none` if logging is disabled, so that parsing it returns the same [LoggingConfig].
+impl Display for LoggingConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.destinations().is_empty() {
+            return write!(f, "{DEST_NONE}");
+        }
+        for (index, destination) in self.destinations().iter().enumerate() {
+            if index > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{destination}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Format a destination without its options
+fn format_destination(destination: &LogDestination) -> String {
+    match destination {
+        LogDestination::Stderr => DEST_STDERR.to_string(),
+        LogDestination::Syslog => DEST_SYSLOG.to_string(),
+        LogDestination::File { path, .. } => format!(
+            "{DEST_FILE}:{}",
+            quote_allowing_colons(&path.to_string_lossy())
+        ),
+        LogDestination::Memory(name) => format!("{DEST_MEMORY}:{}", quote_allowing_colons(name)),
+        LogDestination::Fd(fd) => format!("{DEST_FD}:{fd}"),
+        LogDestination::Pipe(command) => {
+            format!("{DEST_PIPE}:{}", quote_allowing_colons(command))
+        }
+        LogDestination::RingBuffer { capacity, sink } => {
+            format!("{DEST_RINGBUFFER}:{capacity}:{}", format_destination(sink))
+        }
+        LogDestination::Tcp { address, format } => {
+            let scheme = match format {
+                LogFormat::Text => DEST_TCP,
+                LogFormat::Json => DEST_TCP_JSON,
+            };
+            format!(
+                "{scheme}:{}",
+                quote_allowing_colons(&format!("//{address}"))
+            )
+        }
+        LogDestination::Udp { address, format } => {
+            let scheme = match format {
+                LogFormat::Text => DEST_UDP,
+                LogFormat::Json => DEST_UDP_JSON,
+            };
+            format!(
+                "{scheme}:{}",
+                quote_allowing_colons(&format!("//{address}"))
+            )
+        }
+        LogDestination::Unix { path, format } => {
+            let scheme = match format {
+                LogFormat::Text => DEST_UNIX,
+                LogFormat::Json => DEST_UNIX_JSON,
+            };
+            format!(
+                "{scheme}:{}",
+                quote_allowing_colons(&path.to_string_lossy())
+            )
+        }
+        LogDestination::Gelf { address, transport } => {
+            let scheme = match transport {
+                GelfTransport::Udp { compress: false } => DEST_GELF_UDP,
+                GelfTransport::Udp { compress: true } => DEST_GELF_UDP_GZIP,
+                GelfTransport::Tcp => DEST_GELF_TCP,
+            };
+            format!(
+                "{scheme}:{}",
+                quote_allowing_colons(&format!("//{address}"))
+            )
+        }
+        LogDestination::Otlp { endpoint, encoding } => {
+            let scheme = match encoding {
+                OtlpEncoding::Protobuf => DEST_OTLP,
+                OtlpEncoding::Json => DEST_OTLP_JSON,
+            };
+            format!("{scheme}:{}", quote_allowing_colons(endpoint))
+        }
+        LogDestination::Custom { scheme, argument } if argument.is_empty() => scheme.clone(),
+        LogDestination::Custom { scheme, argument } => {
+            format!("{scheme}:{}", quote_allowing_colons(argument))
+        }
+    }
+}
+
+/// Format a filter as an option in the syntax of [apply_options], e.g. `exclude=/healthcheck/`.
+fn format_filter(filter: &LogFilter) -> String {
+    let key = match (filter.kind, filter.field) {
+        (LogFilterKind::Include, LogFilterField::Message) => "include",
+        (LogFilterKind::Exclude, LogFilterField::Message) => "exclude",
+        (LogFilterKind::Include, LogFilterField::Target) => "include_target",
+        (LogFilterKind::Exclude, LogFilterField::Target) => "exclude_target",
+    };
+    let value = match &filter.pattern {
+        LogFilterPattern::Regex(regex) => format!("/{regex}/"),
+        // Substrings between slashes would be parsed as regular expressions, and a leading `~` would be removed
+        LogFilterPattern::Substring(substring)
+            if substring.starts_with('~')
+                || (substring.len() >= 2
+                    && substring.starts_with('/')
+                    && substring.ends_with('/')) =>
+        {
+            format!("~{substring}")
+        }
+        LogFilterPattern::Substring(substring) => substring.clone(),
+    };
+    format!("{key}={}", quote(&value))
+}
+
+/// Format the options of a destination that differ from the defaults, in the syntax of [apply_options].
+fn format_options(destination: &LogDestination) -> Vec<String> {
+    match destination {
+        LogDestination::File { options, .. } => {
+            let FileOptions {
+                format,
+                create_dirs,
+                mode,
+                truncate,
+                lock,
+                fsync,
+                min_free_space,
+                max_size,
+            } = options;
+            let mut result = Vec::new();
+            if *format == LogFormat::Json {
+                result.push("format=json".to_string());
+            }
+            if *create_dirs {
+                result.push("create_dirs".to_string());
+            }
+            if let Some(mode) = mode {
+                result.push(format!("mode={mode:o}"));
+            }
+            if *truncate {
+                result.push("truncate".to_string());
+            }
+            match lock {
+                FileLock::Disabled => {}
+                FileLock::Fail => result.push("lock=fail".to_string()),
+                FileLock::Wait => result.push("lock=wait".to_string()),
+                FileLock::Suffix => result.push("lock=suffix".to_string()),
+            }
+            match fsync {
+                FsyncPolicy::Never => {}
+                FsyncPolicy::Always => result.push("fsync=always".to_string()),
+                FsyncPolicy::Errors => result.push("fsync=errors".to_string()),
+                FsyncPolicy::Periodic(interval) => result.push(format!(
+                    "fsync={}",
+                    quote(&humantime::format_duration(*interval).to_string())
+                )),
+            }
+            if let Some(min_free_space) = min_free_space {
+                result.push(format!("min_free={min_free_space}"));
+            }
+            if let Some(max_size) = max_size {
+                result.push(format!("max_size={max_size}"));
+            }
+            result
+        }
+        LogDestination::RingBuffer { sink, .. } => format_options(sink),
+        _ => Vec::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
@@ -315,6 +1080,8 @@ mod tests {
             Some(LogDestinationConfig {
                 level: None,
                 destination: destination.0,
+                most_severe: None,
+                filters: Vec::new(),
             }),
             config
         );
@@ -328,7 +1095,62 @@ mod tests {
         assert_eq!(
             Some(LogDestinationConfig {
                 level: Some(level.0),
-                destination: destination.0
+                destination: destination.0,
+                most_severe: None,
+                filters: Vec::new(),
+            }),
+            config
+        );
+    }
+
+    #[rstest]
+    #[case(LevelFilter::Off, "OFF")]
+    #[case(LevelFilter::Off, "off")]
+    #[case(LevelFilter::Off, "0")]
+    #[case(LevelFilter::Error, "err")]
+    #[case(LevelFilter::Error, "FATAL")]
+    #[case(LevelFilter::Error, "crit")]
+    #[case(LevelFilter::Error, "critical")]
+    #[case(LevelFilter::Error, "1")]
+    #[case(LevelFilter::Warn, "warning")]
+    #[case(LevelFilter::Warn, "2")]
+    #[case(LevelFilter::Info, "3")]
+    #[case(LevelFilter::Debug, "4")]
+    #[case(LevelFilter::Trace, "verbose")]
+    #[case(LevelFilter::Trace, "5")]
+    fn level_aliases(#[case] level: LevelFilter, #[case] alias: &str) {
+        let config = parse_config_definition(&format!("{alias}:{DEST_STDERR}")).unwrap();
+        assert_eq!(
+            Some(LogDestinationConfig {
+                level: Some(level),
+                destination: LogDestination::Stderr,
+                most_severe: None,
+                filters: Vec::new(),
+            }),
+            config
+        );
+    }
+
+    #[rstest]
+    #[case("DEBUG..INFO", Some(LevelFilter::Debug), Level::Info)]
+    #[case("info..debug", Some(LevelFilter::Debug), Level::Info)]
+    #[case("TRACE..ERROR", Some(LevelFilter::Trace), Level::Error)]
+    #[case("=WARN", Some(LevelFilter::Warn), Level::Warn)]
+    #[case("=warning", Some(LevelFilter::Warn), Level::Warn)]
+    #[case("..INFO", None, Level::Info)]
+    #[case("4..3", Some(LevelFilter::Debug), Level::Info)]
+    fn level_ranges(
+        #[case] range: &str,
+        #[case] level: Option<LevelFilter>,
+        #[case] most_severe: Level,
+    ) {
+        let config = parse_config_definition(&format!("{range}:{DEST_FILE}:/path")).unwrap();
+        assert_eq!(
+            Some(LogDestinationConfig {
+                level,
+                destination: LogDestination::file("/path"),
+                most_severe: Some(most_severe),
+                filters: Vec::new(),
             }),
             config
         );
@@ -342,7 +1164,9 @@ mod tests {
         assert_eq!(
             LogDestinationConfig {
                 level: None,
-                destination: LogDestination::File("/path/to/file".into()),
+                destination: LogDestination::file("/path/to/file"),
+                most_severe: None,
+                filters: Vec::new(),
             },
             config,
         );
@@ -357,7 +1181,9 @@ mod tests {
         assert_eq!(
             LogDestinationConfig {
                 level: Some(level.0),
-                destination: LogDestination::File("/path/to/file".into()),
+                destination: LogDestination::file("/path/to/file"),
+                most_severe: None,
+                filters: Vec::new(),
             },
             config,
         );
@@ -371,7 +1197,9 @@ mod tests {
         assert_eq!(
             LogDestinationConfig {
                 level: None,
-                destination: LogDestination::File("/path/:to/:file".into()),
+                destination: LogDestination::file("/path/:to/:file"),
+                most_severe: None,
+                filters: Vec::new(),
             },
             config,
         );
@@ -386,7 +1214,9 @@ mod tests {
         assert_eq!(
             LogDestinationConfig {
                 level: Some(level.0),
-                destination: LogDestination::File("/path/:to/:file".into()),
+                destination: LogDestination::file("/path/:to/:file"),
+                most_severe: None,
+                filters: Vec::new(),
             },
             config,
         );
@@ -398,7 +1228,9 @@ mod tests {
         assert_eq!(
             LogDestinationConfig {
                 level: None,
-                destination: LogDestination::File(":".into()),
+                destination: LogDestination::file(":"),
+                most_severe: None,
+                filters: Vec::new(),
             },
             config,
         );
@@ -413,7 +1245,9 @@ mod tests {
         assert_eq!(
             LogDestinationConfig {
                 level: Some(level.0),
-                destination: LogDestination::File(":".into()),
+                destination: LogDestination::file(":"),
+                most_severe: None,
+                filters: Vec::new(),
             },
             config,
         );
@@ -425,7 +1259,9 @@ mod tests {
         assert_eq!(
             LogDestinationConfig {
                 level: None,
-                destination: LogDestination::File("::".into()),
+                destination: LogDestination::file("::"),
+                most_severe: None,
+                filters: Vec::new(),
             },
             config,
         );
@@ -440,63 +1276,1113 @@ mod tests {
         assert_eq!(
             LogDestinationConfig {
                 level: Some(level.0),
-                destination: LogDestination::File("::".into()),
+                destination: LogDestination::file("::"),
+                most_severe: None,
+                filters: Vec::new(),
             },
             config,
         );
     }
 
-    mod errors {
-        use super::*;
+    #[test]
+    fn memory_destination_without_level() {
+        let config = parse_config_definition("memory:my-buffer")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            LogDestinationConfig {
+                level: None,
+                destination: LogDestination::Memory("my-buffer".to_string()),
+                most_severe: None,
+                filters: Vec::new(),
+            },
+            config,
+        );
+    }
 
-        #[test]
-        fn empty() {
-            let error = parse_config_definition("").unwrap_err();
-            assert_eq!(
-                "Invalid empty log destination. Choose stderr, syslog, file, or none",
-                error.to_string()
-            );
-        }
+    #[apply(level)]
+    #[rstest]
+    fn memory_destination_with_level(level: (LevelFilter, &str)) {
+        let config = parse_config_definition(&format!("{}:memory:my-buffer", level.1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            LogDestinationConfig {
+                level: Some(level.0),
+                destination: LogDestination::Memory("my-buffer".to_string()),
+                most_severe: None,
+                filters: Vec::new(),
+            },
+            config,
+        );
+    }
 
-        #[apply(level)]
-        #[rstest]
-        fn empty_destination_with_filter(level: (LevelFilter, &str)) {
-            let error = parse_config_definition(&format!("{}:", level.1)).unwrap_err();
-            assert_eq!(
-                "Invalid empty log destination. Choose stderr, syslog, file, or none",
-                error.to_string()
-            );
-        }
+    #[rstest]
+    #[case("memory:")]
+    #[case("memory:\"\"")]
+    fn memory_destination_with_empty_name(#[case] input: &str) {
+        let config = parse_config_definition(input).unwrap().unwrap();
+        assert_eq!(LogDestination::Memory(String::new()), config.destination);
+    }
 
-        #[apply(destination)]
-        #[rstest]
-        fn empty_filter(destination: (LogDestination, &str)) {
-            let error = parse_config_definition(&format!(":{}", destination.1)).unwrap_err();
-            assert_eq!(
-                "Invalid empty log level filter. Choose ERROR, WARN, INFO, DEBUG, or TRACE",
-                error.to_string()
-            );
-        }
+    #[apply(level)]
+    #[rstest]
+    fn fd_destination(level: (LevelFilter, &str)) {
+        let config = parse_config_definition(&format!("{}:fd:3", level.1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            LogDestinationConfig {
+                level: Some(level.0),
+                destination: LogDestination::Fd(3),
+                most_severe: None,
+                filters: Vec::new(),
+            },
+            config,
+        );
+        let config = parse_config_definition("fd:0").unwrap().unwrap();
+        assert_eq!(
+            LogDestinationConfig {
+                level: None,
+                destination: LogDestination::Fd(0),
+                most_severe: None,
+                filters: Vec::new(),
+            },
+            config,
+        );
+    }
 
-        #[apply(level)]
-        #[rstest]
-        fn only_filter(level: (LevelFilter, &str)) {
-            let error = parse_config_definition(level.1).unwrap_err();
-            assert_eq!(
-                format!(
-                    "Expected log destination but found level filter `{}`. Please add a destination. Example: `--log {}:stderr`",
+    #[rstest]
+    fn pipe_destination(
+        #[values("logger -t myapp", "gzip > /path/to/log.gz", "nc localhost:9000")] command: &str,
+    ) {
+        let config = parse_config_definition(&format!("DEBUG:pipe:{command}"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            LogDestinationConfig {
+                level: Some(LevelFilter::Debug),
+                destination: LogDestination::Pipe(command.to_string()),
+                most_severe: None,
+                filters: Vec::new(),
+            },
+            config,
+        );
+    }
+
+    #[rstest]
+    fn ring_buffer_destination(
+        #[values(
+            (LogDestination::Stderr, "stderr"),
+            (LogDestination::file("/path/:to/file"), "file:/path/:to/file")
+        )]
+        sink: (LogDestination, &str),
+    ) {
+        let config = parse_config_definition(&format!("TRACE:ringbuffer:1000:{}", sink.1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            LogDestinationConfig {
+                level: Some(LevelFilter::Trace),
+                destination: LogDestination::RingBuffer {
+                    capacity: 1000,
+                    sink: Box::new(sink.0),
+                },
+                most_severe: None,
+                filters: Vec::new(),
+            },
+            config,
+        );
+    }
+
+    #[rstest]
+    fn network_destination(
+        #[values(
+            ("tcp", LogFormat::Text),
+            ("tcp+json", LogFormat::Json),
+            ("udp", LogFormat::Text),
+            ("UDP+JSON", LogFormat::Json)
+        )]
+        scheme: (&str, LogFormat),
+        #[values("localhost:9000", "127.0.0.1:514", "[::1]:9000")] address: &str,
+    ) {
+        let config = parse_config_definition(&format!("INFO:{}://{address}", scheme.0))
+            .unwrap()
+            .unwrap();
+        let destination = if scheme.0.to_ascii_lowercase().starts_with("tcp") {
+            LogDestination::Tcp {
+                address: address.to_string(),
+                format: scheme.1,
+            }
+        } else {
+            LogDestination::Udp {
+                address: address.to_string(),
+                format: scheme.1,
+            }
+        };
+        assert_eq!(
+            LogDestinationConfig {
+                level: Some(LevelFilter::Info),
+                destination,
+                most_severe: None,
+                filters: Vec::new(),
+            },
+            config,
+        );
+    }
+
+    #[rstest]
+    fn gelf_destination(
+        #[values(
+            ("gelf+udp", GelfTransport::Udp { compress: false }),
+            ("gelf+udp+gzip", GelfTransport::Udp { compress: true }),
+            ("GELF+TCP", GelfTransport::Tcp)
+        )]
+        scheme: (&str, GelfTransport),
+    ) {
+        let config = parse_config_definition(&format!("INFO:{}://graylog:12201", scheme.0))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            LogDestinationConfig {
+                level: Some(LevelFilter::Info),
+                destination: LogDestination::Gelf {
+                    address: "graylog:12201".to_string(),
+                    transport: scheme.1,
+                },
+                most_severe: None,
+                filters: Vec::new(),
+            },
+            config,
+        );
+    }
+
+    #[rstest]
+    fn otlp_destination(
+        #[values(("otlp", OtlpEncoding::Protobuf), ("otlp+json", OtlpEncoding::Json))] scheme: (
+            &str,
+            OtlpEncoding,
+        ),
+        #[values(
+            "http://localhost:4318",
+            "https://collector.example.com/v1/logs",
+            "http://[::1]:4318"
+        )]
+        endpoint: &str,
+    ) {
+        let config = parse_config_definition(&format!("INFO:{}:{endpoint}", scheme.0))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            LogDestinationConfig {
+                level: Some(LevelFilter::Info),
+                destination: LogDestination::Otlp {
+                    endpoint: endpoint.to_string(),
+                    encoding: scheme.1,
+                },
+                most_severe: None,
+                filters: Vec::new(),
+            },
+            config,
+        );
+    }
+
+    #[rstest]
+    fn unix_destination(
+        #[values(("unix", LogFormat::Text), ("unix+json", LogFormat::Json))] scheme: (
+            &str,
+            LogFormat,
+        ),
+    ) {
+        let config = parse_config_definition(&format!("WARN:{}:/run/my:socket", scheme.0))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            LogDestinationConfig {
+                level: Some(LevelFilter::Warn),
+                destination: LogDestination::Unix {
+                    path: "/run/my:socket".into(),
+                    format: scheme.1,
+                },
+                most_severe: None,
+                filters: Vec::new(),
+            },
+            config,
+        );
+    }
+
+    mod custom {
+        use super::*;
+        use crate::LogSink;
+
+        struct TestSink;
+
+        impl LogSink for TestSink {
+            fn validate(&self, argument: &str) -> Result<(), String> {
+                if argument.is_empty() {
+                    Err("The test sink requires a topic".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+
+            fn build(&self, _argument: &str) -> anyhow::Result<Box<dyn log::Log>> {
+                anyhow::bail!("The test sink can't be built")
+            }
+        }
+
+        #[apply(level)]
+        #[rstest]
+        fn custom_destination_with_level(level: (LevelFilter, &str)) {
+            crate::register_sink("parser-tests-queue", TestSink);
+            let config =
+                parse_config_definition(&format!("{}:Parser-Tests-Queue:topic:a", level.1))
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(
+                LogDestinationConfig {
+                    level: Some(level.0),
+                    destination: LogDestination::Custom {
+                        scheme: "parser-tests-queue".to_string(),
+                        argument: "topic:a".to_string(),
+                    },
+                    most_severe: None,
+                    filters: Vec::new(),
+                },
+                config,
+            );
+        }
+
+        #[test]
+        fn custom_destination_without_level() {
+            crate::register_sink("parser-tests-queue", TestSink);
+            let config = parse_config_definition("parser-tests-queue:topic")
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                LogDestinationConfig {
+                    level: None,
+                    destination: LogDestination::Custom {
+                        scheme: "parser-tests-queue".to_string(),
+                        argument: "topic".to_string(),
+                    },
+                    most_severe: None,
+                    filters: Vec::new(),
+                },
+                config,
+            );
+        }
+
+        #[test]
+        fn custom_destination_validation_error() {
+            crate::register_sink("parser-tests-queue", TestSink);
+            let error = parse_config_definition("INFO:parser-tests-queue").unwrap_err();
+            assert_eq!("The test sink requires a topic", error.to_string());
+        }
+
+        #[test]
+        fn unregistered_custom_destination() {
+            let error =
+                parse_config_definition("INFO:parser-tests-unregistered:topic").unwrap_err();
+            assert_eq!(
+                "Invalid log destination `parser-tests-unregistered`. Choose stderr, syslog, file, fd, pipe, memory, ringbuffer, tcp, udp, unix, gelf+udp, gelf+tcp, otlp, or none",
+                error.to_string()
+            );
+        }
+    }
+
+    mod options {
+        use super::*;
+        use crate::{FileOptions, config::FsyncPolicy};
+
+        #[apply(level)]
+        #[rstest]
+        fn file_destination_with_options(level: (LevelFilter, &str)) {
+            let config = parse_config_definition(&format!(
+                "{}:file:/path/to/file;format=json,fsync=errors",
+                level.1
+            ))
+            .unwrap()
+            .unwrap();
+            assert_eq!(
+                LogDestinationConfig {
+                    level: Some(level.0),
+                    destination: LogDestination::File {
+                        path: "/path/to/file".into(),
+                        options: FileOptions {
+                            format: LogFormat::Json,
+                            fsync: FsyncPolicy::Errors,
+                            ..FileOptions::default()
+                        },
+                    },
+                    most_severe: None,
+                    filters: Vec::new(),
+                },
+                config,
+            );
+        }
+
+        #[test]
+        fn file_destination_with_escaped_separator() {
+            let config = parse_config_definition("file:/path/to/a\\;b.log;truncate")
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                LogDestination::File {
+                    path: "/path/to/a;b.log".into(),
+                    options: FileOptions {
+                        truncate: true,
+                        ..FileOptions::default()
+                    },
+                },
+                config.destination,
+            );
+        }
+
+        #[test]
+        fn network_destination_with_options() {
+            let config = parse_config_definition("INFO:tcp://localhost:9000;format=json")
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                LogDestination::Tcp {
+                    address: "localhost:9000".to_string(),
+                    format: LogFormat::Json,
+                },
+                config.destination,
+            );
+        }
+
+        #[test]
+        fn ring_buffer_destination_with_options() {
+            let config =
+                parse_config_definition("ringbuffer:100:file:/path/to/a\\;b.log;create_dirs")
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(
+                LogDestination::RingBuffer {
+                    capacity: 100,
+                    sink: Box::new(LogDestination::File {
+                        path: "/path/to/a;b.log".into(),
+                        options: FileOptions {
+                            create_dirs: true,
+                            ..FileOptions::default()
+                        },
+                    }),
+                },
+                config.destination,
+            );
+        }
+
+        fn filter(
+            kind: LogFilterKind,
+            field: LogFilterField,
+            pattern: LogFilterPattern,
+        ) -> LogFilter {
+            LogFilter {
+                kind,
+                field,
+                pattern,
+            }
+        }
+
+        #[test]
+        fn filters() {
+            let config = parse_config_definition(
+                "INFO:stderr;exclude=/healthcheck|metrics/,exclude=ping,include=~/api/,include_target=myapp,exclude_target=\"/^myapp::db$/\"",
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(
+                LogDestinationConfig {
+                    level: Some(LevelFilter::Info),
+                    destination: LogDestination::Stderr,
+                    most_severe: None,
+                    filters: vec![
+                        filter(
+                            LogFilterKind::Exclude,
+                            LogFilterField::Message,
+                            LogFilterPattern::Regex("healthcheck|metrics".to_string())
+                        ),
+                        filter(
+                            LogFilterKind::Exclude,
+                            LogFilterField::Message,
+                            LogFilterPattern::Substring("ping".to_string())
+                        ),
+                        filter(
+                            LogFilterKind::Include,
+                            LogFilterField::Message,
+                            LogFilterPattern::Substring("/api/".to_string())
+                        ),
+                        filter(
+                            LogFilterKind::Include,
+                            LogFilterField::Target,
+                            LogFilterPattern::Substring("myapp".to_string())
+                        ),
+                        filter(
+                            LogFilterKind::Exclude,
+                            LogFilterField::Target,
+                            LogFilterPattern::Regex("^myapp::db$".to_string())
+                        ),
+                    ],
+                },
+                config
+            );
+        }
+
+        #[test]
+        fn filters_and_destination_options() {
+            let config = parse_config_definition("file:/path;exclude=x,truncate,include=/y/")
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                LogDestinationConfig {
+                    level: None,
+                    destination: LogDestination::File {
+                        path: "/path".into(),
+                        options: FileOptions {
+                            truncate: true,
+                            ..FileOptions::default()
+                        },
+                    },
+                    most_severe: None,
+                    filters: vec![
+                        filter(
+                            LogFilterKind::Exclude,
+                            LogFilterField::Message,
+                            LogFilterPattern::Substring("x".to_string())
+                        ),
+                        filter(
+                            LogFilterKind::Include,
+                            LogFilterField::Message,
+                            LogFilterPattern::Regex("y".to_string())
+                        ),
+                    ],
+                },
+                config
+            );
+        }
+
+        #[test]
+        fn pipe_destination_with_escaped_separator() {
+            let config = parse_config_definition("pipe:sort\\; cat")
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                LogDestination::Pipe("sort; cat".to_string()),
+                config.destination
+            );
+        }
+
+        #[rstest]
+        #[case("none;truncate", "The none log destination doesn't support options")]
+        #[case(
+            "INFO:stderr;color=never",
+            "Unknown option `color` for the stderr log destination. Choose include, exclude, include_target, exclude_target"
+        )]
+        #[case(
+            "file:/path/to/file;rotate=10MB",
+            "Unknown option `rotate` for the file log destination. Choose format, create_dirs, mode, truncate, append, lock, fsync, min_free, max_size, include, exclude, include_target, exclude_target"
+        )]
+        #[case(
+            "pipe:sort; cat",
+            "Unknown option `cat` for the pipe log destination. Choose include, exclude, include_target, exclude_target"
+        )]
+        #[case(
+            "invalid;truncate",
+            "Invalid log destination `invalid`. Choose stderr, syslog, file, fd, pipe, memory, ringbuffer, tcp, udp, unix, gelf+udp, gelf+tcp, otlp, or none"
+        )]
+        #[case(
+            "stderr;exclude=/(/",
+            "Invalid value `/(/` for log destination option `exclude`. Expected a substring, or a regular expression like `/^GET /health/`"
+        )]
+        #[case(
+            "stderr;exclude",
+            "Log destination option `exclude` requires a value, e.g. `exclude=...`"
+        )]
+        #[case("none;exclude=x", "The none log destination doesn't support options")]
+        fn option_errors(#[case] input: &str, #[case] expected: &str) {
+            assert_eq!(
+                expected,
+                parse_config_definition(input).unwrap_err().to_string()
+            );
+        }
+    }
+
+    mod display {
+        use super::*;
+        use std::time::Duration;
+
+        fn config(level: Option<LevelFilter>, destination: LogDestination) -> LogDestinationConfig {
+            LogDestinationConfig {
+                level,
+                destination,
+                most_severe: None,
+                filters: Vec::new(),
+            }
+        }
+
+        fn file_with_options(path: &str, options: FileOptions) -> LogDestination {
+            LogDestination::File {
+                path: path.into(),
+                options,
+            }
+        }
+
+        #[rstest]
+        #[case(config(None, LogDestination::Stderr), "stderr")]
+        #[case(
+            config(Some(LevelFilter::Trace), LogDestination::Syslog),
+            "TRACE:syslog"
+        )]
+        #[case(
+            config(Some(LevelFilter::Info), LogDestination::file("/path/to/file")),
+            "INFO:file:/path/to/file"
+        )]
+        #[case(
+            config(None, LogDestination::file("C:\\logs\\app.log")),
+            "file:C:\\logs\\app.log"
+        )]
+        #[case(
+            config(None, LogDestination::file("/path/with:colon")),
+            "file:/path/with:colon"
+        )]
+        #[case(
+            config(None, LogDestination::file("/path/with;semicolon")),
+            "file:\"/path/with;semicolon\""
+        )]
+        #[case(
+            config(None, LogDestination::file("/path with \"quotes\"")),
+            "file:\"/path with \"\"quotes\"\"\""
+        )]
+        #[case(
+            config(None, LogDestination::file("C:\\logs\\")),
+            "file:\"C:\\logs\\\""
+        )]
+        #[case(
+            config(Some(LevelFilter::Debug), file_with_options("/path", FileOptions {
+                format: LogFormat::Json,
+                create_dirs: true,
+                mode: Some(0o640),
+                truncate: true,
+                lock: FileLock::Suffix,
+                fsync: FsyncPolicy::Periodic(Duration::from_millis(1500)),
+                min_free_space: Some(1_000_000),
+                max_size: Some(500),
+            })),
+            "DEBUG:file:/path;format=json,create_dirs,mode=640,truncate,lock=suffix,fsync=\"1s 500ms\",min_free=1000000,max_size=500"
+        )]
+        #[case(config(None, file_with_options("/path", FileOptions { lock: FileLock::Wait, fsync: FsyncPolicy::Errors, ..FileOptions::default() })), "file:/path;lock=wait,fsync=errors")]
+        #[case(config(None, file_with_options("/path", FileOptions { lock: FileLock::Fail, fsync: FsyncPolicy::Always, ..FileOptions::default() })), "file:/path;lock=fail,fsync=always")]
+        #[case(config(None, LogDestination::Memory("name".to_string())), "memory:name")]
+        #[case(config(Some(LevelFilter::Warn), LogDestination::Fd(3)), "WARN:fd:3")]
+        #[case(config(None, LogDestination::Pipe("gzip > /path/to/file.gz".to_string())), "pipe:\"gzip > /path/to/file.gz\"")]
+        #[case(config(None, LogDestination::Pipe("logger".to_string())), "pipe:logger")]
+        #[case(
+            config(Some(LevelFilter::Trace), LogDestination::RingBuffer {
+                capacity: 1000,
+                sink: Box::new(file_with_options("/path/with:colon", FileOptions { truncate: true, ..FileOptions::default() })),
+            }),
+            "TRACE:ringbuffer:1000:file:/path/with:colon;truncate"
+        )]
+        #[case(config(None, LogDestination::RingBuffer { capacity: 5, sink: Box::new(LogDestination::Stderr) }), "ringbuffer:5:stderr")]
+        #[case(config(None, LogDestination::Tcp { address: "localhost:9000".to_string(), format: LogFormat::Text }), "tcp://localhost:9000")]
+        #[case(config(None, LogDestination::Tcp { address: "localhost:9000".to_string(), format: LogFormat::Json }), "tcp+json://localhost:9000")]
+        #[case(config(None, LogDestination::Udp { address: "[::1]:9000".to_string(), format: LogFormat::Text }), "udp://[::1]:9000")]
+        #[case(config(None, LogDestination::Udp { address: "[::1]:9000".to_string(), format: LogFormat::Json }), "udp+json://[::1]:9000")]
+        #[case(config(None, LogDestination::Unix { path: "/run/log.sock".into(), format: LogFormat::Text }), "unix:/run/log.sock")]
+        #[case(config(None, LogDestination::Unix { path: "/run/log.sock".into(), format: LogFormat::Json }), "unix+json:/run/log.sock")]
+        #[case(config(None, LogDestination::Gelf { address: "graylog:12201".to_string(), transport: GelfTransport::Udp { compress: false } }), "gelf+udp://graylog:12201")]
+        #[case(config(None, LogDestination::Gelf { address: "graylog:12201".to_string(), transport: GelfTransport::Udp { compress: true } }), "gelf+udp+gzip://graylog:12201")]
+        #[case(config(None, LogDestination::Gelf { address: "graylog:12201".to_string(), transport: GelfTransport::Tcp }), "gelf+tcp://graylog:12201")]
+        #[case(config(None, LogDestination::Otlp { endpoint: "http://localhost:4318".to_string(), encoding: OtlpEncoding::Protobuf }), "otlp:http://localhost:4318")]
+        #[case(config(None, LogDestination::Otlp { endpoint: "https://otlp/v1/logs?key=a,b".to_string(), encoding: OtlpEncoding::Json }), "otlp+json:\"https://otlp/v1/logs?key=a,b\"")]
+        #[case(LogDestinationConfig { level: Some(LevelFilter::Debug), destination: LogDestination::Stderr, most_severe: Some(Level::Info), filters: Vec::new() }, "DEBUG..INFO:stderr")]
+        #[case(LogDestinationConfig { level: Some(LevelFilter::Warn), destination: LogDestination::Stderr, most_severe: Some(Level::Warn), filters: Vec::new() }, "=WARN:stderr")]
+        #[case(LogDestinationConfig { level: None, destination: LogDestination::Stderr, most_severe: Some(Level::Info), filters: Vec::new() }, "..INFO:stderr")]
+        #[case(
+            LogDestinationConfig {
+                level: Some(LevelFilter::Info),
+                destination: LogDestination::file("/path"),
+                most_severe: None,
+                filters: vec![
+                    LogFilter { kind: LogFilterKind::Exclude, field: LogFilterField::Message, pattern: LogFilterPattern::Regex("health, ready".to_string()) },
+                    LogFilter { kind: LogFilterKind::Include, field: LogFilterField::Target, pattern: LogFilterPattern::Substring("myapp".to_string()) },
+                ],
+            },
+            "INFO:file:/path;exclude=\"/health, ready/\",include_target=myapp"
+        )]
+        fn display(#[case] config: LogDestinationConfig, #[case] expected: &str) {
+            assert_eq!(expected, config.to_string());
+        }
+
+        struct AnyArgumentSink;
+
+        impl crate::LogSink for AnyArgumentSink {
+            fn build(&self, _argument: &str) -> anyhow::Result<Box<dyn log::Log>> {
+                anyhow::bail!("The any-argument sink can't be built")
+            }
+        }
+
+        /// The variant of a destination. The match is exhaustive, so new variants can't be forgotten in [every_destination].
+        fn variant_index(destination: &LogDestination) -> usize {
+            match destination {
+                LogDestination::Stderr => 0,
+                LogDestination::Syslog => 1,
+                LogDestination::File { .. } => 2,
+                LogDestination::Pipe(_) => 3,
+                LogDestination::Memory(_) => 4,
+                LogDestination::Fd(_) => 5,
+                LogDestination::RingBuffer { .. } => 6,
+                LogDestination::Tcp { .. } => 7,
+                LogDestination::Udp { .. } => 8,
+                LogDestination::Unix { .. } => 9,
+                LogDestination::Gelf { .. } => 10,
+                LogDestination::Otlp { .. } => 11,
+                LogDestination::Custom { .. } => 12,
+            }
+        }
+        const VARIANT_COUNT: usize = 13;
+
+        /// Every variant of [LogDestination], with values that need quoting or escaping and all option values
+        fn every_destination() -> Vec<LogDestination> {
+            crate::register_sink("display-tests", AnyArgumentSink);
+            let all_file_options = FileOptions {
+                format: LogFormat::Json,
+                create_dirs: true,
+                mode: Some(0o640),
+                truncate: true,
+                lock: FileLock::Suffix,
+                fsync: FsyncPolicy::Periodic(Duration::from_millis(1500)),
+                min_free_space: Some(1_000_000),
+                max_size: Some(500),
+            };
+            let mut destinations = vec![
+                LogDestination::Stderr,
+                LogDestination::Syslog,
+                LogDestination::Pipe("gzip > \"/path/to/file.gz\"; echo a,b".to_string()),
+                LogDestination::Pipe("logger".to_string()),
+                LogDestination::Memory("name".to_string()),
+                LogDestination::Memory("name:with;special,chars".to_string()),
+                LogDestination::Memory(String::new()),
+                LogDestination::Fd(3),
+                LogDestination::RingBuffer {
+                    capacity: 5,
+                    sink: Box::new(LogDestination::Stderr),
+                },
+                LogDestination::RingBuffer {
+                    capacity: 1000,
+                    sink: Box::new(file_with_options(
+                        "/path/with:colon;semicolon",
+                        all_file_options.clone(),
+                    )),
+                },
+                LogDestination::Otlp {
+                    endpoint: "https://otlp/v1/logs?key=a,b".to_string(),
+                    encoding: OtlpEncoding::Json,
+                },
+                LogDestination::Otlp {
+                    endpoint: "http://localhost:4318".to_string(),
+                    encoding: OtlpEncoding::Protobuf,
+                },
+                LogDestination::custom("display-tests", "").unwrap(),
+                LogDestination::custom("Display-Tests", "topic:with;special,chars").unwrap(),
+            ];
+            for path in [
+                "/path/to/file",
+                "/path/with:colon",
+                "/path/with;semicolon",
+                "/path with \"quotes\"",
+                "C:\\logs\\app.log",
+                "C:\\logs\\",
+            ] {
+                destinations.push(LogDestination::file(path));
+            }
+            for options in [
+                all_file_options,
+                FileOptions {
+                    lock: FileLock::Wait,
+                    fsync: FsyncPolicy::Errors,
+                    ..FileOptions::default()
+                },
+                FileOptions {
+                    lock: FileLock::Fail,
+                    fsync: FsyncPolicy::Always,
+                    ..FileOptions::default()
+                },
+            ] {
+                destinations.push(file_with_options("/path", options));
+            }
+            for format in [LogFormat::Text, LogFormat::Json] {
+                destinations.push(LogDestination::Tcp {
+                    address: "localhost:9000".to_string(),
+                    format,
+                });
+                destinations.push(LogDestination::Udp {
+                    address: "[::1]:9000".to_string(),
+                    format,
+                });
+                destinations.push(LogDestination::Unix {
+                    path: "/run/log:1.sock".into(),
+                    format,
+                });
+            }
+            for transport in [
+                GelfTransport::Udp { compress: false },
+                GelfTransport::Udp { compress: true },
+                GelfTransport::Tcp,
+            ] {
+                destinations.push(LogDestination::Gelf {
+                    address: "graylog:12201".to_string(),
+                    transport,
+                });
+            }
+            destinations
+        }
+
+        /// Every level filter and level range, see [LogDestinationConfig::with_level_range]
+        fn every_level(destination: &LogDestination) -> Vec<LogDestinationConfig> {
+            let mut configs = vec![LogDestinationConfig::new(destination.clone(), None)];
+            for level in LevelFilter::iter() {
+                configs.push(LogDestinationConfig::new(destination.clone(), Some(level)));
+            }
+            for from in std::iter::once(None).chain(Level::iter().map(Some)) {
+                for to in Level::iter() {
+                    configs.push(LogDestinationConfig::with_level_range(
+                        destination.clone(),
+                        from,
+                        to,
+                    ));
+                }
+            }
+            configs
+        }
+
+        fn every_filter() -> Vec<LogFilter> {
+            let mut filters = Vec::new();
+            for kind in [LogFilterKind::Include, LogFilterKind::Exclude] {
+                for field in [LogFilterField::Message, LogFilterField::Target] {
+                    for pattern in [
+                        LogFilterPattern::Substring("health".to_string()),
+                        LogFilterPattern::Substring("a,b=\"c\";d".to_string()),
+                        LogFilterPattern::Substring("/api/".to_string()),
+                        LogFilterPattern::Substring("~".to_string()),
+                        LogFilterPattern::Regex("^GET /health, ready$".to_string()),
+                    ] {
+                        filters.push(LogFilter {
+                            kind,
+                            field,
+                            pattern,
+                        });
+                    }
+                }
+            }
+            filters
+        }
+
+        #[test]
+        fn round_trip() {
+            let destinations = every_destination();
+            for index in 0..VARIANT_COUNT {
+                assert!(
+                    destinations
+                        .iter()
+                        .any(|destination| variant_index(destination) == index),
+                    "Variant {index} is missing in every_destination()"
+                );
+            }
+            for destination in &destinations {
+                for mut config in every_level(destination) {
+                    for filters in [Vec::new(), every_filter()] {
+                        config.filters = filters;
+                        let display = config.to_string();
+                        let parsed = display
+                            .parse::<LogDestinationConfig>()
+                            .unwrap_or_else(|err| panic!("Failed to parse `{display}`: {err}"));
+                        assert_eq!(config, parsed, "{display}");
+                    }
+                }
+            }
+        }
+
+        #[rstest]
+        #[case("/a.b/", "stderr;exclude=~/a.b/")]
+        #[case("//", "stderr;exclude=~//")]
+        #[case("~/app", "stderr;exclude=~~/app")]
+        #[case("/", "stderr;exclude=/")]
+        fn substring_filter_between_slashes(#[case] substring: &str, #[case] expected: &str) {
+            let config = LogDestinationConfig {
+                level: None,
+                destination: LogDestination::Stderr,
+                most_severe: None,
+                filters: vec![LogFilter {
+                    kind: LogFilterKind::Exclude,
+                    field: LogFilterField::Message,
+                    pattern: LogFilterPattern::Substring(substring.to_string()),
+                }],
+            };
+            assert_eq!(expected, config.to_string());
+            assert_eq!(config, config.to_string().parse().unwrap());
+        }
+
+        #[test]
+        fn none_isnt_a_destination() {
+            assert_eq!(
+                "`none` disables logging and isn't a log destination. Parse it as a LoggingConfig instead",
+                "none"
+                    .parse::<LogDestinationConfig>()
+                    .unwrap_err()
+                    .to_string()
+            );
+        }
+
+        #[rstest]
+        #[case(LoggingConfig::disabled(), "none")]
+        #[case(LoggingConfig::new(vec![config(None, LogDestination::Stderr)]), "stderr")]
+        #[case(
+            LoggingConfig::new(vec![
+                config(Some(LevelFilter::Info), LogDestination::Stderr),
+                config(None, LogDestination::file("/path with spaces/file")),
+                config(Some(LevelFilter::Debug), LogDestination::Pipe("gzip > /a.gz".to_string())),
+            ]),
+            "INFO:stderr file:\"/path with spaces/file\" DEBUG:pipe:\"gzip > /a.gz\""
+        )]
+        fn logging_config_round_trip(#[case] config: LoggingConfig, #[case] expected: &str) {
+            assert_eq!(expected, config.to_string());
+            assert_eq!(config, expected.parse::<LoggingConfig>().unwrap());
+        }
+
+        #[rstest]
+        #[case("none stderr", LoggingConfig::new(vec![config(None, LogDestination::Stderr)]))]
+        #[case("  INFO:stderr\n", LoggingConfig::new(vec![config(Some(LevelFilter::Info), LogDestination::Stderr)]))]
+        #[case("none none", LoggingConfig::disabled())]
+        fn logging_config_from_str(#[case] input: &str, #[case] expected: LoggingConfig) {
+            assert_eq!(expected, input.parse::<LoggingConfig>().unwrap());
+        }
+
+        #[rstest]
+        #[case(
+            "",
+            "Invalid empty logging configuration. Use `none` to disable logging"
+        )]
+        #[case(
+            "stderr invalid",
+            "Invalid log destination `invalid`. Choose stderr, syslog, file, fd, pipe, memory, ringbuffer, tcp, udp, unix, gelf+udp, gelf+tcp, otlp, or none"
+        )]
+        #[case(
+            "file:\"/path",
+            "Unclosed `\"` in `file:\"/path`. Use `\\\"` for a literal `\"`, or `\"\"` inside of quotes"
+        )]
+        fn logging_config_errors(#[case] input: &str, #[case] expected: &str) {
+            assert_eq!(
+                expected,
+                input.parse::<LoggingConfig>().unwrap_err().to_string()
+            );
+        }
+    }
+
+    mod quoting {
+        use super::*;
+        use crate::FileOptions;
+        use crate::tokenizer::quote;
+
+        #[rstest]
+        fn file_path_round_trip(
+            #[values(
+                "/path/to/file",
+                "/path/with:colon",
+                "/path/with;semicolon",
+                "/path/with\"quote\"",
+                "C:\\logs\\app.log",
+                "C:\\logs\\",
+                "/path with spaces/file"
+            )]
+            path: &str,
+        ) {
+            let config = parse_config_definition(&format!("INFO:file:{};truncate", quote(path)))
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                LogDestination::File {
+                    path: path.into(),
+                    options: FileOptions {
+                        truncate: true,
+                        ..FileOptions::default()
+                    },
+                },
+                config.destination
+            );
+        }
+
+        #[rstest]
+        #[case("file:\"/path/with:colon\"", "/path/with:colon")]
+        #[case("file:/path/with\\:colon", "/path/with:colon")]
+        #[case("file:\"/a;b\"", "/a;b")]
+        #[case("file:\"C:\\logs\\\"", "C:\\logs\\")]
+        #[case("file:C:\\logs\\app.log", "C:\\logs\\app.log")]
+        #[case("\"file\":\"/path\"", "/path")]
+        fn quoted_file_path(#[case] input: &str, #[case] expected: &str) {
+            let config = parse_config_definition(input).unwrap().unwrap();
+            assert_eq!(LogDestination::file(expected), config.destination);
+        }
+
+        #[test]
+        fn quoted_ring_buffer_sink() {
+            let config =
+                parse_config_definition("ringbuffer:100:file:\"/path/with:colon;semicolon\"")
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(
+                LogDestination::RingBuffer {
+                    capacity: 100,
+                    sink: Box::new(LogDestination::file("/path/with:colon;semicolon")),
+                },
+                config.destination
+            );
+        }
+
+        #[test]
+        fn quoted_pipe_command() {
+            let config = parse_config_definition("pipe:\"logger -t 'a:b; c'\"")
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                LogDestination::Pipe("logger -t 'a:b; c'".to_string()),
+                config.destination
+            );
+        }
+
+        #[rstest]
+        #[case("pipe:logger -t \\\"my app\\\"", "logger -t \"my app\"")]
+        #[case("pipe:\"logger -t \"\"my app\"\"\"", "logger -t \"my app\"")]
+        #[case("INFO:pipe:gzip > \\\"a b.gz\\\"", "gzip > \"a b.gz\"")]
+        fn pipe_command_with_quotes(#[case] input: &str, #[case] expected: &str) {
+            let config = parse_config_definition(input).unwrap().unwrap();
+            assert_eq!(
+                LogDestination::Pipe(expected.to_string()),
+                config.destination
+            );
+        }
+
+        #[rstest]
+        #[case("pipe:logger -t \"my app\"", "logger -t \"my app\"")]
+        #[case("INFO:pipe:gzip > \"a b.gz\"", "gzip > \"a b.gz\"")]
+        #[case("=WARN:pipe:\"gzip\" > a.gz", "\"gzip\" > a.gz")]
+        #[case("pipe:a:\"b\"c", "a:\"b\"c")]
+        fn pipe_command_with_inner_quotes(#[case] input: &str, #[case] command: &str) {
+            let error = parse_config_definition(input).unwrap_err();
+            assert_eq!(
+                format!(
+                    "Quotes inside of the pipe command `{command}` would be removed before the shell runs it. Escape them like `pipe:logger -t \\\"my app\\\"`, or quote the whole command like `pipe:\"logger -t \"\"my app\"\"\"`"
+                ),
+                error.to_string()
+            );
+        }
+
+        #[test]
+        fn quoted_option_value() {
+            let config = parse_config_definition("file:/path;format=\"json\",mode=\"600\"")
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                LogDestination::File {
+                    path: "/path".into(),
+                    options: FileOptions {
+                        format: LogFormat::Json,
+                        mode: Some(0o600),
+                        ..FileOptions::default()
+                    },
+                },
+                config.destination
+            );
+        }
+
+        #[rstest]
+        #[case("file:\"/path")]
+        #[case("INFO:file:/path;format=\"json")]
+        fn unclosed_quote(#[case] input: &str) {
+            let error = parse_config_definition(input).unwrap_err().to_string();
+            assert!(error.starts_with("Unclosed `\"` in `"), "{error}");
+        }
+    }
+
+    mod errors {
+        use super::*;
+
+        #[test]
+        fn empty() {
+            let error = parse_config_definition("").unwrap_err();
+            assert_eq!(
+                "Invalid empty log destination. Choose stderr, syslog, file, fd, pipe, memory, ringbuffer, tcp, udp, unix, gelf+udp, gelf+tcp, otlp, or none",
+                error.to_string()
+            );
+        }
+
+        #[apply(level)]
+        #[rstest]
+        fn empty_destination_with_filter(level: (LevelFilter, &str)) {
+            let error = parse_config_definition(&format!("{}:", level.1)).unwrap_err();
+            assert_eq!(
+                "Invalid empty log destination. Choose stderr, syslog, file, fd, pipe, memory, ringbuffer, tcp, udp, unix, gelf+udp, gelf+tcp, otlp, or none",
+                error.to_string()
+            );
+        }
+
+        #[apply(destination)]
+        #[rstest]
+        fn empty_filter(destination: (LogDestination, &str)) {
+            let error = parse_config_definition(&format!(":{}", destination.1)).unwrap_err();
+            assert_eq!(
+                "Invalid empty log level filter. Choose OFF, ERROR, WARN, INFO, DEBUG, or TRACE",
+                error.to_string()
+            );
+        }
+
+        #[apply(level)]
+        #[rstest]
+        fn only_filter(level: (LevelFilter, &str)) {
+            let error = parse_config_definition(level.1).unwrap_err();
+            assert_eq!(
+                format!(
+                    "Expected log destination but found level filter `{}`. Please add a destination. Example: `--log {}:stderr`",
                     level.1, level.1,
                 ),
                 error.to_string()
             );
         }
 
+        #[rstest]
+        #[case(
+            "DEBUG..INFO",
+            "Expected log destination but found level filter `DEBUG..INFO`. Please add a destination. Example: `--log DEBUG..INFO:stderr`"
+        )]
+        #[case(
+            "OFF..INFO:stderr",
+            "Level ranges can't include OFF. Use `OFF:destination` to disable a destination"
+        )]
+        #[case(
+            "=OFF:stderr",
+            "Level ranges can't include OFF. Use `OFF:destination` to disable a destination"
+        )]
+        #[case(
+            "DEBUG..INVALID:stderr",
+            "Invalid log level filter `DEBUG..INVALID`. Choose OFF, ERROR, WARN, INFO, DEBUG, or TRACE"
+        )]
+        #[case(
+            "INFO:=WARN",
+            "Expected log destination but found level filter `=WARN`. Please add a destination. Example: `--log INFO:stderr`"
+        )]
+        fn invalid_level_range(#[case] input: &str, #[case] expected: &str) {
+            let error = parse_config_definition(input).unwrap_err();
+            assert_eq!(expected, error.to_string());
+        }
+
         #[apply(destination)]
         #[rstest]
         fn invalid_level(destination: (LogDestination, &str)) {
             let error = parse_config_definition(&format!("invalid:{}", destination.1)).unwrap_err();
             assert_eq!(
-                "Invalid log level filter `invalid`. Choose ERROR, WARN, INFO, DEBUG, or TRACE",
+                "Invalid log level filter `invalid`. Choose OFF, ERROR, WARN, INFO, DEBUG, or TRACE",
                 error.to_string()
             );
         }
@@ -506,7 +2392,7 @@ mod tests {
         fn invalid_destination_with_level(level: (LevelFilter, &str)) {
             let error = parse_config_definition(&format!("{}:invalid", level.1)).unwrap_err();
             assert_eq!(
-                "Invalid log destination `invalid`. Choose stderr, syslog, file, or none",
+                "Invalid log destination `invalid`. Choose stderr, syslog, file, fd, pipe, memory, ringbuffer, tcp, udp, unix, gelf+udp, gelf+tcp, otlp, or none",
                 error.to_string()
             );
         }
@@ -516,17 +2402,86 @@ mod tests {
             let error =
                 parse_config_definition(&format!("{LEVEL_ERROR_UPPER}{DEST_STDERR}")).unwrap_err();
             assert_eq!(
-                "Invalid log destination `ERRORstderr`. Choose stderr, syslog, file, or none",
+                "Invalid log destination `ERRORstderr`. Did you mean `ERROR:stderr`? Choose stderr, syslog, file, fd, pipe, memory, ringbuffer, tcp, udp, unix, gelf+udp, gelf+tcp, otlp, or none",
                 error.to_string()
             );
         }
 
+        #[rstest]
+        #[case(
+            "stdrr",
+            "Invalid log destination `stdrr`. Did you mean `stderr`? Choose"
+        )]
+        #[case(
+            "stdout",
+            "Invalid log destination `stdout`. Did you mean `stderr`? Choose"
+        )]
+        #[case(
+            "INFO:stdrr",
+            "Invalid log destination `stdrr`. Did you mean `stderr`? Choose"
+        )]
+        #[case(
+            "INFO:journald",
+            "Invalid log destination `journald`. Did you mean `syslog`? Choose"
+        )]
+        #[case(
+            "WARRN:stderr",
+            "Invalid log level filter `WARRN`. Did you mean `WARN`? Choose"
+        )]
+        #[case(
+            "severe:stderr",
+            "Invalid log level filter `severe`. Did you mean `ERROR`? Choose"
+        )]
+        #[case(
+            "warnng:stderr",
+            "Invalid log level filter `warnng`. Did you mean `WARN`? Choose"
+        )]
+        #[case(
+            "fatl:stderr",
+            "Invalid log level filter `fatl`. Did you mean `ERROR`? Choose"
+        )]
+        #[case(
+            "verbos:stderr",
+            "Invalid log level filter `verbos`. Did you mean `TRACE`? Choose"
+        )]
+        #[case(
+            "warnng:stdrr",
+            "Invalid log configuration `warnng:stdrr`. Did you mean `WARN:stderr`? Examples"
+        )]
+        #[case(
+            "debgu:stderr",
+            "Invalid log level filter `debgu`. Did you mean `DEBUG`? Choose"
+        )]
+        #[case(
+            "fiel:/path",
+            "Invalid log configuration `fiel:/path`. Did you mean `file:/path`? Examples"
+        )]
+        #[case(
+            "WARRN:stdrr",
+            "Invalid log configuration `WARRN:stdrr`. Did you mean `WARN:stderr`? Examples"
+        )]
+        #[case(
+            "WARNING:stdrr",
+            "Invalid log destination `stdrr`. Did you mean `stderr`? Choose"
+        )]
+        #[case("debug:stderr", "")]
+        #[case("fatal:stderr", "")]
+        fn did_you_mean(#[case] input: &str, #[case] expected_start: &str) {
+            match parse_config_definition(input) {
+                Ok(_) => assert!(expected_start.is_empty(), "{input} parsed successfully"),
+                Err(error) => assert!(
+                    error.to_string().starts_with(expected_start),
+                    "{error} doesn't start with {expected_start}"
+                ),
+            }
+        }
+
         #[test]
         fn partially_matching_filter() {
             // Regression test. A previous version misparsed this as a filter since it started like the error filter with 'E' and matched the first letter, but we should actually treat this as an invalid log destination
             let error = parse_config_definition("ega").unwrap_err();
             assert_eq!(
-                "Invalid log destination `ega`. Choose stderr, syslog, file, or none",
+                "Invalid log destination `ega`. Choose stderr, syslog, file, fd, pipe, memory, ringbuffer, tcp, udp, unix, gelf+udp, gelf+tcp, otlp, or none",
                 error.to_string()
             );
         }
@@ -535,7 +2490,7 @@ mod tests {
         fn invalid_destination_without_filter() {
             let error = parse_config_definition("invalid").unwrap_err();
             assert_eq!(
-                "Invalid log destination `invalid`. Choose stderr, syslog, file, or none",
+                "Invalid log destination `invalid`. Choose stderr, syslog, file, fd, pipe, memory, ringbuffer, tcp, udp, unix, gelf+udp, gelf+tcp, otlp, or none",
                 error.to_string()
             );
         }
@@ -584,6 +2539,159 @@ mod tests {
             );
         }
 
+        #[test]
+        fn memory_destination_without_level_without_name() {
+            let error = parse_config_definition(DEST_MEMORY).unwrap_err();
+            assert_eq!(
+                "Memory log destination requires a name. Example: `--log memory:name`",
+                error.to_string()
+            );
+        }
+
+        #[apply(level)]
+        #[rstest]
+        fn memory_destination_with_level_without_name(level: (LevelFilter, &str)) {
+            let error = parse_config_definition(&format!("{}:memory", level.1)).unwrap_err();
+            assert_eq!(
+                format!(
+                    "Memory log destination requires a name. Example: `--log {}:memory:name`",
+                    level.1
+                ),
+                error.to_string()
+            );
+        }
+
+        #[rstest]
+        fn fd_destination_without_number(
+            #[values("", ":", ":-1", ":abc", ":3:4", ":/dev/fd/3")] suffix: &str,
+        ) {
+            let error = parse_config_definition(&format!("WARN:fd{suffix}")).unwrap_err();
+            assert_eq!(
+                "File descriptor log destination requires a file descriptor number. Example: `--log WARN:fd:3`",
+                error.to_string()
+            );
+        }
+
+        #[rstest]
+        fn pipe_destination_without_command(#[values("pipe", "pipe:", "pipe:  ")] input: &str) {
+            let error = parse_config_definition(input).unwrap_err();
+            assert_eq!(
+                "Pipe log destination requires a command. Example: `--log \"pipe:logger -t myapp\"`",
+                error.to_string()
+            );
+        }
+
+        #[rstest]
+        fn ring_buffer_destination_without_capacity_or_sink(
+            #[values("ringbuffer", "ringbuffer:1000")] input: &str,
+        ) {
+            let error = parse_config_definition(input).unwrap_err();
+            assert_eq!(
+                "Ring buffer log destination requires a capacity and a sink. Example: `--log ringbuffer:1000:file:/path/to/file`",
+                error.to_string()
+            );
+        }
+
+        #[rstest]
+        fn ring_buffer_destination_with_invalid_capacity(
+            #[values("0", "-5", "abc", "")] capacity: &str,
+        ) {
+            let error = parse_config_definition(&format!("TRACE:ringbuffer:{capacity}:stderr"))
+                .unwrap_err();
+            assert_eq!(
+                format!(
+                    "Invalid ring buffer capacity `{capacity}`. The capacity must be a positive number. Example: `--log TRACE:ringbuffer:1000:file:/path/to/file`"
+                ),
+                error.to_string()
+            );
+        }
+
+        #[rstest]
+        fn ring_buffer_destination_with_unsupported_sink(
+            #[values("syslog", "memory:name", "none", "INFO:stderr", "ringbuffer:10:stderr")]
+            sink: &str,
+        ) {
+            let error = parse_config_definition(&format!("ringbuffer:1000:{sink}")).unwrap_err();
+            assert_eq!(
+                "Ring buffer sink must be stderr or file without a level filter. Example: `--log ringbuffer:1000:file:/path/to/file`",
+                error.to_string()
+            );
+        }
+
+        #[test]
+        fn ring_buffer_destination_with_invalid_sink() {
+            let error = parse_config_definition("ringbuffer:1000:invalid").unwrap_err();
+            assert_eq!(
+                "Invalid log destination `invalid`. Choose stderr, syslog, file, fd, pipe, memory, ringbuffer, tcp, udp, unix, gelf+udp, gelf+tcp, otlp, or none",
+                error.to_string()
+            );
+        }
+
+        #[rstest]
+        fn network_destination_with_invalid_address(
+            #[values(
+                "",
+                ":",
+                "localhost:9000",
+                "//localhost",
+                "//localhost:",
+                "//:9000",
+                "//localhost:0",
+                "//localhost:65536",
+                "//localhost:port"
+            )]
+            address: &str,
+        ) {
+            let error = parse_config_definition(&format!("tcp:{address}")).unwrap_err();
+            assert_eq!(
+                "TCP log destination requires an address with a host and a port. Example: `--log tcp://localhost:9000`",
+                error.to_string()
+            );
+            let error = parse_config_definition(&format!("DEBUG:udp+json:{address}")).unwrap_err();
+            assert_eq!(
+                "UDP log destination requires an address with a host and a port. Example: `--log DEBUG:udp+json://localhost:9000`",
+                error.to_string()
+            );
+        }
+
+        #[rstest]
+        fn gelf_destination_with_invalid_address(
+            #[values("gelf+udp", "gelf+udp+gzip:", "gelf+tcp:graylog:12201")] input: &str,
+        ) {
+            let error = parse_config_definition(&format!("INFO:{input}")).unwrap_err();
+            let scheme = input.split(':').next().unwrap();
+            assert_eq!(
+                format!(
+                    "GELF log destination requires an address with a host and a port. Example: `--log INFO:{scheme}://localhost:12201`"
+                ),
+                error.to_string()
+            );
+        }
+
+        #[rstest]
+        fn otlp_destination_with_invalid_endpoint(
+            #[values("", "localhost:4318", "http://", "http:///v1/logs", "ftp://localhost")]
+            endpoint: &str,
+        ) {
+            let error = parse_config_definition(&format!("otlp+json:{endpoint}")).unwrap_err();
+            assert_eq!(
+                "OTLP log destination requires an http:// or https:// endpoint. Example: `--log otlp+json:http://localhost:4318`",
+                error.to_string()
+            );
+        }
+
+        #[rstest]
+        fn unix_destination_without_path(#[values("unix", "unix:", "unix+json:")] input: &str) {
+            let error = parse_config_definition(input).unwrap_err();
+            let scheme = input.trim_end_matches(':');
+            assert_eq!(
+                format!(
+                    "Unix socket log destination requires a path. Example: `--log {scheme}:/path/to/socket`"
+                ),
+                error.to_string()
+            );
+        }
+
         #[apply(level)]
         #[apply(level2)]
         #[rstest]
@@ -0,0 +1,209 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow, bail};
+
+/// Expand the placeholders in a log file path:
+/// * `{pid}`: the process id
+/// * `{exe}`: the process name
+/// * `{date}`: the current UTC date as `YYYY-MM-DD`
+/// * `{hostname}`: the host name
+/// * `{user}`: the user name, from the `USER` or `USERNAME` environment variable
+/// * `~` at the start of the path: the home directory
+/// * `$VAR` or `${VAR}`: the value of the environment variable `VAR`
+///
+/// Literal braces can be written as `{{` and `}}`. Paths that aren't valid UTF-8 are returned unchanged.
+pub fn expand_path(path: &Path, process_name: &str) -> Result<PathBuf> {
+    let Some(template) = path.to_str() else {
+        return Ok(path.to_path_buf());
+    };
+    let placeholder = |name: &str| -> Result<String> {
+        match name {
+            "pid" => Ok(std::process::id().to_string()),
+            "exe" => Ok(process_name.to_string()),
+            "date" => Ok(today()),
+            "hostname" => hostname::get()
+                .map(|hostname| hostname.to_string_lossy().into_owned())
+                .map_err(|err| anyhow!("Failed to get the hostname: {err}")),
+            "user" => env_var("USER")
+                .or_else(|| env_var("USERNAME"))
+                .ok_or_else(|| {
+                    anyhow!("Failed to get the user name, neither USER nor USERNAME is set")
+                }),
+            _ => bail!(
+                "Unknown placeholder `{{{name}}}`. Choose {{pid}}, {{exe}}, {{date}}, {{hostname}}, or {{user}}"
+            ),
+        }
+    };
+    expand(template, placeholder, env_var)
+        .map(PathBuf::from)
+        .map_err(|err| anyhow!("Invalid log file path `{template}`: {err}"))
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+/// The current UTC date as `YYYY-MM-DD`
+fn today() -> String {
+    let now = humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string();
+    now[..10].to_string()
+}
+
+fn expand(
+    template: &str,
+    placeholder: impl Fn(&str) -> Result<String>,
+    env_var: impl Fn(&str) -> Option<String>,
+) -> Result<String> {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    if let Some(after_tilde) = rest.strip_prefix('~')
+        && (after_tilde.is_empty() || after_tilde.starts_with(['/', '\\']))
+    {
+        let home = env_var("HOME")
+            .or_else(|| env_var("USERPROFILE"))
+            .ok_or_else(|| anyhow!("Failed to expand `~`, HOME is not set"))?;
+        result.push_str(&home);
+        rest = after_tilde;
+    }
+
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '{' if rest.starts_with('{') => {
+                result.push('{');
+                rest = &rest[1..];
+            }
+            '}' if rest.starts_with('}') => {
+                result.push('}');
+                rest = &rest[1..];
+            }
+            '{' => {
+                let Some((name, after)) = rest.split_once('}') else {
+                    bail!("Unclosed `{{`. Use `{{{{` for a literal `{{`");
+                };
+                result.push_str(&placeholder(name)?);
+                rest = after;
+            }
+            '}' => bail!("Unmatched `}}`. Use `}}}}` for a literal `}}`"),
+            '$' => {
+                let (name, after) = if let Some(braced) = rest.strip_prefix('{') {
+                    let Some((name, after)) = braced.split_once('}') else {
+                        bail!("Unclosed `${{`");
+                    };
+                    (name, after)
+                } else {
+                    let len = rest
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                        .unwrap_or(rest.len());
+                    rest.split_at(len)
+                };
+                if name.is_empty() {
+                    // Not a variable, e.g. a `$` at the end of a file name
+                    result.push('$');
+                    continue;
+                }
+                let value = env_var(name)
+                    .ok_or_else(|| anyhow!("Environment variable `{name}` is not set"))?;
+                result.push_str(&value);
+                rest = after;
+            }
+            c => result.push(c),
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn test_placeholder(name: &str) -> Result<String> {
+        match name {
+            "pid" => Ok("1234".to_string()),
+            "exe" => Ok("my-app".to_string()),
+            _ => bail!("Unknown placeholder `{{{name}}}`"),
+        }
+    }
+
+    fn test_env_var(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/alice".to_string()),
+            "LOG_DIR" => Some("/var/log".to_string()),
+            _ => None,
+        }
+    }
+
+    #[rstest]
+    #[case("/path/to/file", "/path/to/file")]
+    #[case("/tmp/{exe}-{pid}.log", "/tmp/my-app-1234.log")]
+    #[case("~", "/home/alice")]
+    #[case("~/logs/{exe}.log", "/home/alice/logs/my-app.log")]
+    #[case("/tmp/~/file~", "/tmp/~/file~")]
+    #[case("~alice/file", "~alice/file")]
+    #[case("$LOG_DIR/{exe}.log", "/var/log/my-app.log")]
+    #[case("${LOG_DIR}_old/file", "/var/log_old/file")]
+    #[case("$LOG_DIR-old/file", "/var/log-old/file")]
+    #[case("/tmp/price$", "/tmp/price$")]
+    #[case("/tmp/a$-b", "/tmp/a$-b")]
+    #[case("/tmp/{{literal}}", "/tmp/{literal}")]
+    #[case("/tmp/ümlaut-{pid}", "/tmp/ümlaut-1234")]
+    fn expands(#[case] template: &str, #[case] expected: &str) {
+        assert_eq!(
+            expected,
+            expand(template, test_placeholder, test_env_var).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case("/tmp/{unknown}", "Unknown placeholder `{unknown}`")]
+    #[case("/tmp/{pid", "Unclosed `{`. Use `{{` for a literal `{`")]
+    #[case("/tmp/pid}", "Unmatched `}`. Use `}}` for a literal `}`")]
+    #[case("$UNSET/file", "Environment variable `UNSET` is not set")]
+    #[case("${LOG_DIR/file", "Unclosed `${`")]
+    fn errors(#[case] template: &str, #[case] expected: &str) {
+        assert_eq!(
+            expected,
+            expand(template, test_placeholder, test_env_var)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn home_not_set() {
+        assert_eq!(
+            "Failed to expand `~`, HOME is not set",
+            expand("~/file", test_placeholder, |_| None)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn expand_path_with_real_values() {
+        let path = expand_path(Path::new("/tmp/{exe}-{pid}-{date}.log"), "my-app").unwrap();
+        let expected_regex = format!(
+            r"^/tmp/my-app-{}-\d{{4}}-\d{{2}}-\d{{2}}\.log$",
+            std::process::id()
+        );
+        assert!(
+            regex::Regex::new(&expected_regex)
+                .unwrap()
+                .is_match(path.to_str().unwrap()),
+            "path: {}",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn expand_path_error_mentions_path() {
+        assert_eq!(
+            "Invalid log file path `/tmp/{unknown}`: Unknown placeholder `{unknown}`. Choose {pid}, {exe}, {date}, {hostname}, or {user}",
+            expand_path(Path::new("/tmp/{unknown}"), "my-app")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
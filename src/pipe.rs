@@ -0,0 +1,199 @@
+use std::io::{ErrorKind, Write as _};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context as _, Result};
+
+use super::line_logger::LineWriter;
+use super::network::Backoff;
+
+/// A child process that exits sooner than this is considered to be failing,
+/// and is restarted with exponential backoff instead of right away.
+const MIN_UPTIME: Duration = Duration::from_secs(1);
+
+/// Writes log lines to the stdin of a child process, like Apache's piped logs.
+///
+/// The command is run by the shell, so it can use redirections like `gzip > log.gz`.
+/// If the child process exits, it is restarted when the next line is written.
+/// Log lines written while a failing child process waits to be restarted are dropped.
+pub struct PipeLineWriter {
+    command: String,
+    child: Option<RunningChild>,
+    backoff: Backoff,
+}
+
+struct RunningChild {
+    process: Child,
+    stdin: ChildStdin,
+    started: Instant,
+}
+
+impl PipeLineWriter {
+    /// Create the writer and spawn the child process, so that a command that can't be started is reported right away.
+    pub fn new(command: &str) -> Result<Self> {
+        let child = spawn(command)
+            .with_context(|| format!("Failed to spawn log pipe command `{command}`"))?;
+        Ok(Self {
+            command: command.to_string(),
+            child: Some(child),
+            backoff: Backoff::new(),
+        })
+    }
+
+    fn stdin(&mut self) -> std::io::Result<&mut ChildStdin> {
+        if let Some(child) = &mut self.child
+            && !matches!(child.process.try_wait(), Ok(None))
+        {
+            self.discard_child();
+        }
+        if self.child.is_none() {
+            if !self.backoff.may_attempt() {
+                return Err(std::io::Error::new(
+                    ErrorKind::NotConnected,
+                    "Waiting for restart backoff",
+                ));
+            }
+            match spawn(&self.command) {
+                Ok(child) => self.child = Some(child),
+                Err(err) => {
+                    self.backoff.failed();
+                    return Err(err);
+                }
+            }
+        }
+        Ok(&mut self
+            .child
+            .as_mut()
+            .expect("We just spawned the child")
+            .stdin)
+    }
+
+    /// Stop using the current child process because it exited or doesn't accept input anymore.
+    fn discard_child(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            drop(child.stdin);
+            let _ = child.process.kill();
+            let _ = child.process.wait();
+            if child.started.elapsed() < MIN_UPTIME {
+                self.backoff.failed();
+            } else {
+                self.backoff.reset();
+            }
+        }
+    }
+}
+
+fn spawn(command: &str) -> std::io::Result<RunningChild> {
+    let mut process = shell_command(command).stdin(Stdio::piped()).spawn()?;
+    let stdin = process.stdin.take().expect("stdin is piped");
+    Ok(RunningChild {
+        process,
+        stdin,
+        started: Instant::now(),
+    })
+}
+
+#[cfg(unix)]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+impl LineWriter for PipeLineWriter {
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let line = format!("{line}\n");
+        let result = self.stdin()?.write_all(line.as_bytes());
+        if result.is_err() {
+            // The child probably exited after we checked. The next line restarts it.
+            self.discard_child();
+        }
+        result
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.child {
+            Some(child) => child.stdin.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for PipeLineWriter {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            // Closing stdin signals the end of the log to the child, e.g. so gzip can finish the file
+            drop(child.stdin);
+            let _ = child.process.wait();
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn wait_for_exit(writer: &mut PipeLineWriter) {
+        writer.child.as_mut().unwrap().process.wait().unwrap();
+    }
+
+    #[test]
+    fn writes_lines_to_stdin() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("logfile");
+        let mut writer = PipeLineWriter::new(&format!("cat > '{}'", path.display())).unwrap();
+        writer.write_line("first line").unwrap();
+        writer.write_line("second line").unwrap();
+        drop(writer);
+        assert_eq!(
+            "first line\nsecond line\n",
+            std::fs::read_to_string(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn restarts_exited_child() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let path = tempdir.path().join("logfile");
+        // Exits after each line
+        let mut writer =
+            PipeLineWriter::new(&format!("head -n 1 >> '{}'", path.display())).unwrap();
+        writer.write_line("first line").unwrap();
+        wait_for_exit(&mut writer);
+
+        // The child exited right away, so it's only restarted after the backoff
+        let error = writer.write_line("dropped line").unwrap_err();
+        assert_eq!(ErrorKind::NotConnected, error.kind());
+        std::thread::sleep(Duration::from_millis(200));
+        writer.write_line("second line").unwrap();
+        drop(writer);
+        assert_eq!(
+            "first line\nsecond line\n",
+            std::fs::read_to_string(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn restarts_long_running_child_immediately() {
+        let mut writer = PipeLineWriter::new("cat > /dev/null").unwrap();
+        writer.child.as_mut().unwrap().started -= MIN_UPTIME;
+        writer.child.as_mut().unwrap().process.kill().unwrap();
+        wait_for_exit(&mut writer);
+        writer.write_line("line").unwrap();
+    }
+
+    #[test]
+    fn command_not_found() {
+        // The shell starts fine, but exits right away because it can't find the command
+        let mut writer = PipeLineWriter::new("this-command-does-not-exist 2>/dev/null").unwrap();
+        wait_for_exit(&mut writer);
+        assert!(writer.write_line("line").is_err());
+    }
+}
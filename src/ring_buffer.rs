@@ -0,0 +1,297 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, Weak};
+
+use anyhow::{Result, anyhow, bail};
+use log::Log as _;
+
+use super::config::LogDestination;
+use super::file::{FileLogger, open_log_file};
+use super::utils::{ignore_log_error, lock};
+
+/// All ring buffers that are currently alive. They get dumped on panic or when receiving SIGUSR1.
+static RING_BUFFERS: LazyLock<Mutex<Vec<Weak<RingBuffer>>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
+
+/// A [log::Log] implementation that keeps the last `capacity` (already formatted) records in memory
+/// and writes them to its sink when an ERROR is logged, on panic, or on SIGUSR1.
+///
+/// File sinks are written through a [FileLogger], so their [FileOptions](crate::FileOptions) apply like for a `file:` destination.
+pub struct RingBufferLogger {
+    ring_buffer: Arc<RingBuffer>,
+}
+
+impl RingBufferLogger {
+    pub fn new(capacity: usize, sink: &LogDestination) -> Result<Self> {
+        install_triggers()?;
+        let ring_buffer = Arc::new(RingBuffer {
+            // Don't preallocate, the capacity is user input and could be huge
+            records: Mutex::new(VecDeque::new()),
+            capacity,
+            sink: Mutex::new(open_sink(sink)?),
+        });
+        lock(&RING_BUFFERS).push(Arc::downgrade(&ring_buffer));
+        Ok(Self { ring_buffer })
+    }
+}
+
+impl log::Log for RingBufferLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.ring_buffer
+            .push(record.level(), record.args().to_string());
+        if record.level() == log::Level::Error {
+            self.ring_buffer.dump();
+        }
+    }
+
+    fn flush(&self) {
+        // Flushing doesn't dump the records, they're only written out when one of the triggers happens.
+        lock(&self.ring_buffer.sink).flush();
+    }
+}
+
+struct RingBuffer {
+    /// The level of each record is kept, so file sinks can still drop records below WARN while the log file is too large
+    records: Mutex<VecDeque<(log::Level, String)>>,
+    capacity: usize,
+    sink: Mutex<Sink>,
+}
+
+impl RingBuffer {
+    fn push(&self, level: log::Level, line: String) {
+        let mut records = lock(&self.records);
+        if records.len() >= self.capacity {
+            records.pop_front();
+        }
+        records.push_back((level, line));
+    }
+
+    /// Write all records to the sink and remove them from the ring buffer.
+    fn dump(&self) {
+        let records = std::mem::take(&mut *lock(&self.records));
+        let sink = lock(&self.sink);
+        for (level, line) in records {
+            sink.write(level, &line);
+        }
+        sink.flush();
+    }
+}
+
+enum Sink {
+    Stderr,
+    File(FileLogger),
+}
+
+impl Sink {
+    fn write(&self, level: log::Level, line: &str) {
+        match self {
+            Sink::Stderr => ignore_log_error(writeln!(std::io::stderr(), "{line}")),
+            Sink::File(file_logger) => file_logger.log(
+                &log::Record::builder()
+                    .level(level)
+                    .args(format_args!("{line}"))
+                    .build(),
+            ),
+        }
+    }
+
+    fn flush(&self) {
+        match self {
+            Sink::Stderr => ignore_log_error(std::io::stderr().flush()),
+            Sink::File(file_logger) => file_logger.flush(),
+        }
+    }
+}
+
+fn open_sink(sink: &LogDestination) -> Result<Sink> {
+    match sink {
+        LogDestination::Stderr => Ok(Sink::Stderr),
+        LogDestination::File { path, options } => Ok(Sink::File(FileLogger::new(
+            open_log_file(path, options)?,
+            path,
+            options,
+        ))),
+        _ => bail!("Ring buffer log destinations can only write to stderr or a file"),
+    }
+}
+
+fn dump_all() {
+    for ring_buffer in lock(&RING_BUFFERS).iter().filter_map(Weak::upgrade) {
+        ring_buffer.dump();
+    }
+}
+
+/// Install a panic hook and (on unix) a SIGUSR1 handler that dump all ring buffers. This only happens once per process,
+/// later calls return the result of the first one.
+fn install_triggers() -> Result<()> {
+    static INSTALLED: OnceLock<std::result::Result<(), String>> = OnceLock::new();
+    INSTALLED
+        .get_or_init(|| {
+            let previous_hook = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                dump_all();
+                previous_hook(info);
+            }));
+
+            #[cfg(unix)]
+            {
+                let mut signals = signal_hook::iterator::Signals::new([
+                    signal_hook::consts::SIGUSR1,
+                ])
+                .map_err(|err| {
+                    format!(
+                        "Failed to install SIGUSR1 handler for ring buffer log destinations: {err}"
+                    )
+                })?;
+                std::thread::spawn(move || {
+                    for _ in signals.forever() {
+                        dump_all();
+                    }
+                });
+            }
+            Ok(())
+        })
+        .clone()
+        .map_err(|err| anyhow!(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileOptions;
+    use log::Level;
+
+    fn log(logger: &RingBufferLogger, level: Level, message: &str) {
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("{message}"))
+                .level(level)
+                .target("my-test")
+                .build(),
+        );
+    }
+
+    fn file_sink() -> (assert_fs::TempDir, std::path::PathBuf) {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let file = tempdir.path().join("logfile");
+        (tempdir, file)
+    }
+
+    fn read(file: &std::path::Path) -> String {
+        std::fs::read_to_string(file).unwrap_or_default()
+    }
+
+    #[test]
+    fn doesnt_write_without_trigger() {
+        let (_tempdir, file) = file_sink();
+        let logger = RingBufferLogger::new(10, &LogDestination::file(file.clone())).unwrap();
+        log(&logger, Level::Trace, "trace message");
+        log(&logger, Level::Warn, "warn message");
+        logger.flush();
+        assert_eq!("", read(&file));
+    }
+
+    #[test]
+    fn dumps_on_error() {
+        let (_tempdir, file) = file_sink();
+        let logger = RingBufferLogger::new(10, &LogDestination::file(file.clone())).unwrap();
+        log(&logger, Level::Trace, "trace message");
+        log(&logger, Level::Error, "error message");
+        assert_eq!("trace message\nerror message\n", read(&file));
+
+        // The dumped records are removed from the ring buffer and not dumped again
+        log(&logger, Level::Debug, "debug message");
+        log(&logger, Level::Error, "second error message");
+        assert_eq!(
+            "trace message\nerror message\ndebug message\nsecond error message\n",
+            read(&file)
+        );
+    }
+
+    #[test]
+    fn keeps_only_last_records() {
+        let (_tempdir, file) = file_sink();
+        let logger = RingBufferLogger::new(3, &LogDestination::file(file.clone())).unwrap();
+        for i in 0..10 {
+            log(&logger, Level::Info, &format!("message {i}"));
+        }
+        log(&logger, Level::Error, "error message");
+        assert_eq!("message 8\nmessage 9\nerror message\n", read(&file));
+    }
+
+    #[test]
+    fn file_sink_uses_file_options() {
+        let (_tempdir, file) = file_sink();
+        let sink = LogDestination::File {
+            path: file.clone(),
+            options: FileOptions {
+                max_size: Some(20),
+                ..FileOptions::default()
+            },
+        };
+        let logger = RingBufferLogger::new(10, &sink).unwrap();
+        log(&logger, Level::Info, "info message");
+        log(&logger, Level::Info, "second info message");
+        log(&logger, Level::Error, "error message");
+        let logged = read(&file);
+        assert!(logged.starts_with("info message\n"), "{logged}");
+        assert!(
+            logged.contains("exceeds the maximum size of 20 bytes"),
+            "{logged}"
+        );
+        assert!(!logged.contains("second info message"), "{logged}");
+        assert!(logged.ends_with("error message\n"), "{logged}");
+    }
+
+    #[test]
+    fn huge_capacity_isnt_preallocated() {
+        let (_tempdir, file) = file_sink();
+        let logger =
+            RingBufferLogger::new(usize::MAX, &LogDestination::file(file.clone())).unwrap();
+        log(&logger, Level::Info, "info message");
+        log(&logger, Level::Error, "error message");
+        assert_eq!("info message\nerror message\n", read(&file));
+    }
+
+    #[test]
+    fn dumps_on_panic() {
+        let (_tempdir, file) = file_sink();
+        let logger = RingBufferLogger::new(10, &LogDestination::file(file.clone())).unwrap();
+        log(&logger, Level::Info, "info message");
+        let result = std::panic::catch_unwind(|| panic!("test panic"));
+        assert!(result.is_err());
+        assert_eq!("info message\n", read(&file));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dumps_on_sigusr1() {
+        let (_tempdir, file) = file_sink();
+        let logger = RingBufferLogger::new(10, &LogDestination::file(file.clone())).unwrap();
+        log(&logger, Level::Info, "info message");
+        signal_hook::low_level::raise(signal_hook::consts::SIGUSR1).unwrap();
+        // The signal is handled asynchronously on a background thread
+        for _ in 0..100 {
+            if !read(&file).is_empty() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!("info message\n", read(&file));
+    }
+
+    #[test]
+    fn unsupported_sink() {
+        let error = RingBufferLogger::new(10, &LogDestination::Syslog)
+            .err()
+            .unwrap();
+        assert_eq!(
+            "Ring buffer log destinations can only write to stderr or a file",
+            error.to_string()
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// All log sinks registered with [register_sink], by scheme.
+static SINKS: LazyLock<RwLock<HashMap<String, Arc<dyn LogSink>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// A custom log destination. Register it with [register_sink] to make it available as `--log scheme:argument`.
+///
+/// # Example
+/// ```rust
+/// use clap_logflag::LogSink;
+///
+/// struct MyQueue;
+///
+/// struct MyQueueLogger {
+///     topic: String,
+/// }
+///
+/// impl log::Log for MyQueueLogger {
+///     fn enabled(&self, _metadata: &log::Metadata) -> bool {
+///         true
+///     }
+///
+///     fn log(&self, record: &log::Record) {
+///         // Send the record to `self.topic`
+///     }
+///
+///     fn flush(&self) {}
+/// }
+///
+/// impl LogSink for MyQueue {
+///     fn validate(&self, topic: &str) -> Result<(), String> {
+///         if topic.is_empty() {
+///             return Err("The myqueue log destination requires a topic. Example: `--log myqueue:topic`".to_string());
+///         }
+///         Ok(())
+///     }
+///
+///     fn build(&self, topic: &str) -> anyhow::Result<Box<dyn log::Log>> {
+///         // Connect to the queue here and fail if it isn't reachable
+///         Ok(Box::new(MyQueueLogger { topic: topic.to_string() }))
+///     }
+/// }
+///
+/// // Register the sink before parsing the command line arguments. Then, `--log INFO:myqueue:topic` works.
+/// clap_logflag::register_sink("myqueue", MyQueue);
+/// ```
+pub trait LogSink: Send + Sync + 'static {
+    /// Validate the argument following the scheme, e.g. `topic` in `--log INFO:myqueue:topic`.
+    /// The argument is an empty string if there was nothing after the scheme.
+    ///
+    /// This is called while parsing the command line arguments, so any error is shown to the user
+    /// as a command line parsing error. By default, all arguments are accepted.
+    fn validate(&self, argument: &str) -> Result<(), String> {
+        let _ = argument;
+        Ok(())
+    }
+
+    /// Build the logger for a log destination with this sink's scheme.
+    ///
+    /// The returned logger only receives records that pass the level filter of the log destination.
+    /// Records aren't formatted, i.e. `record.args()` is the original log message.
+    fn build(&self, argument: &str) -> anyhow::Result<Box<dyn log::Log>>;
+}
+
+/// Register a custom log destination that can then be used with `--log scheme:argument`
+/// or [LogDestination::custom](crate::LogDestination::custom).
+///
+/// Sinks must be registered before the command line arguments are parsed.
+/// Schemes are case insensitive. Registering a scheme a second time replaces the previously registered sink.
+///
+/// # Panics
+/// Panics if the scheme is empty, contains a `:`, or conflicts with a built-in log destination or log level.
+pub fn register_sink(scheme: &str, sink: impl LogSink) {
+    assert!(
+        !scheme.is_empty() && !scheme.contains(':'),
+        "Invalid log sink scheme `{scheme}`"
+    );
+    assert!(
+        !crate::parser::is_builtin_token(scheme),
+        "Log sink scheme `{scheme}` conflicts with a built-in log destination or log level"
+    );
+    SINKS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(scheme.to_ascii_lowercase(), Arc::new(sink));
+}
+
+/// Get the sink registered for the given scheme.
+pub fn get_sink(scheme: &str) -> Option<Arc<dyn LogSink>> {
+    SINKS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(&scheme.to_ascii_lowercase())
+        .cloned()
+}
+
+/// The schemes of all registered sinks.
+pub fn registered_schemes() -> Vec<String> {
+    SINKS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .keys()
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestSink;
+
+    impl LogSink for TestSink {
+        fn validate(&self, argument: &str) -> Result<(), String> {
+            if argument == "invalid" {
+                Err("Invalid argument".to_string())
+            } else {
+                Ok(())
+            }
+        }
+
+        fn build(&self, _argument: &str) -> anyhow::Result<Box<dyn log::Log>> {
+            anyhow::bail!("The test sink can't be built")
+        }
+    }
+
+    #[test]
+    fn registered_sink_is_found_case_insensitive() {
+        register_sink("sink-tests-case", TestSink);
+        assert!(get_sink("sink-tests-case").is_some());
+        assert!(get_sink("SINK-TESTS-CASE").is_some());
+        assert_eq!(
+            Err("Invalid argument".to_string()),
+            get_sink("sink-tests-case").unwrap().validate("invalid")
+        );
+    }
+
+    #[test]
+    fn unregistered_sink_is_not_found() {
+        assert!(get_sink("sink-tests-unregistered").is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Log sink scheme `stderr` conflicts with a built-in log destination")]
+    fn builtin_destination_cant_be_registered() {
+        register_sink("stderr", TestSink);
+    }
+
+    #[test]
+    #[should_panic(expected = "Log sink scheme `Info` conflicts with a built-in log destination")]
+    fn level_cant_be_registered() {
+        register_sink("Info", TestSink);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid log sink scheme `my:sink`")]
+    fn scheme_with_colon_cant_be_registered() {
+        register_sink("my:sink", TestSink);
+    }
+}
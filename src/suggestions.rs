@@ -0,0 +1,77 @@
+/// Find the candidate closest to the input by edit distance, ignoring case, for "did you mean" hints in error messages.
+///
+/// Candidates that are too different from the input aren't suggested: at most one edit per three characters of the candidate is allowed.
+pub fn closest<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let input = input.to_lowercase();
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(&input, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, candidate)| *distance <= candidate.chars().count() / 3)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The number of insertions, deletions, substitutions and transpositions of adjacent characters
+/// needed to turn one string into the other (optimal string alignment distance).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // distances[i][j] is the distance between the first i characters of a and the first j characters of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution_cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + substitution_cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("", "", 0)]
+    #[case("stderr", "stderr", 0)]
+    #[case("stdrr", "stderr", 1)]
+    #[case("fiel", "file", 1)]
+    #[case("sylsog", "syslog", 1)]
+    #[case("abc", "", 3)]
+    #[case("kitten", "sitting", 3)]
+    #[case("ümlaut", "umlaut", 1)]
+    fn distance(#[case] a: &str, #[case] b: &str, #[case] expected: usize) {
+        assert_eq!(expected, edit_distance(a, b));
+        assert_eq!(expected, edit_distance(b, a));
+    }
+
+    #[rstest]
+    #[case("stdrr", Some("stderr"))]
+    #[case("STDERRR", Some("stderr"))]
+    #[case("fiel", Some("file"))]
+    #[case("sislog", Some("syslog"))]
+    #[case("tpc", Some("tcp"))]
+    #[case("fd", Some("fd"))]
+    #[case("fx", None)]
+    #[case("invalid", None)]
+    #[case("", None)]
+    fn closest_candidate(#[case] input: &str, #[case] expected: Option<&str>) {
+        assert_eq!(
+            expected,
+            closest(input, ["stderr", "syslog", "file", "fd", "tcp", "udp"])
+        );
+    }
+}
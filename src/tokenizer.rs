@@ -0,0 +1,325 @@
+use std::borrow::Cow;
+
+use super::parser::ParseError;
+
+/// Characters that separate the parts of a log definition, and can be escaped with a backslash.
+const SPECIAL_CHARS: [char; 5] = [':', ';', ',', '=', '"'];
+
+/// Split the input at every separator that isn't quoted or escaped. The parts are returned raw, use [unquote] on them.
+///
+/// Quoted text like `"/path/with:colon"` is never split. Inside quotes, `""` is a literal `"` and backslashes have no special meaning,
+/// so Windows paths can be quoted as they are. Outside of quotes, a backslash escapes the following `:`, `;`, `,`, `=` or `"`,
+/// other backslashes are kept as they are.
+pub fn split_unquoted(input: &str, separator: char) -> Result<Vec<&str>, ParseError> {
+    let mut parts = Vec::new();
+    let mut rest = input;
+    while let Some(index) =
+        find_separator(rest, |c| c == separator).ok_or_else(|| unclosed_quote(input))?
+    {
+        parts.push(&rest[..index]);
+        rest = &rest[index + separator.len_utf8()..];
+    }
+    parts.push(rest);
+    Ok(parts)
+}
+
+/// Split the input at whitespace that isn't quoted, skipping empty parts. The parts are returned raw like with [split_unquoted].
+pub fn split_unquoted_whitespace(input: &str) -> Result<Vec<&str>, ParseError> {
+    let mut parts = Vec::new();
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        let index = find_separator(rest, char::is_whitespace)
+            .ok_or_else(|| unclosed_quote(input))?
+            .unwrap_or(rest.len());
+        parts.push(&rest[..index]);
+        rest = rest[index..].trim_start();
+    }
+    Ok(parts)
+}
+
+/// Split the input at the first separator that isn't quoted or escaped, see [split_unquoted].
+pub fn split_once_unquoted(
+    input: &str,
+    separator: char,
+) -> Result<(&str, Option<&str>), ParseError> {
+    Ok(
+        match find_separator(input, |c| c == separator).ok_or_else(|| unclosed_quote(input))? {
+            Some(index) => (
+                &input[..index],
+                Some(&input[index + separator.len_utf8()..]),
+            ),
+            None => (input, None),
+        },
+    )
+}
+
+/// Return the index of the first unquoted separator, or `None` if a quote isn't closed.
+fn find_separator(input: &str, is_separator: impl Fn(char) -> bool) -> Option<Option<usize>> {
+    let mut in_quotes = false;
+    let mut chars = input.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let next = chars.peek().map(|(_, next)| *next);
+        match c {
+            '"' if in_quotes && next == Some('"') => {
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            '\\' if !in_quotes && next.is_some_and(|next| SPECIAL_CHARS.contains(&next)) => {
+                chars.next();
+            }
+            c if is_separator(c) && !in_quotes => return Some(Some(index)),
+            _ => {}
+        }
+    }
+    if in_quotes { None } else { Some(None) }
+}
+
+fn unclosed_quote(input: &str) -> ParseError {
+    ParseError::new(format!(
+        "Unclosed `\"` in `{input}`. Use `\\\"` for a literal `\"`, or `\"\"` inside of quotes"
+    ))
+}
+
+/// Return true if a raw part returned by [split_unquoted] has quotes that don't enclose the whole part, e.g. `gzip > "a b.gz"`.
+pub fn has_inner_quotes(raw: &str) -> bool {
+    let mut in_quotes = false;
+    let mut chars = raw.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let next = chars.peek().map(|(_, next)| *next);
+        match c {
+            '"' if in_quotes && next == Some('"') => {
+                chars.next();
+            }
+            '"' => {
+                let encloses_whole_part = if in_quotes {
+                    index == raw.len() - 1
+                } else {
+                    index == 0
+                };
+                if !encloses_whole_part {
+                    return true;
+                }
+                in_quotes = !in_quotes;
+            }
+            '\\' if !in_quotes && next.is_some_and(|next| SPECIAL_CHARS.contains(&next)) => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Remove the quotes and escapes from a raw part returned by [split_unquoted].
+pub fn unquote(raw: &str) -> Cow<'_, str> {
+    if !raw.contains(['"', '\\']) {
+        return Cow::Borrowed(raw);
+    }
+    let mut result = String::with_capacity(raw.len());
+    let mut in_quotes = false;
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        match c {
+            '"' if in_quotes && next == Some('"') => {
+                result.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            '\\' if !in_quotes && next.is_some_and(|next| SPECIAL_CHARS.contains(&next)) => {
+                result.extend(next);
+                chars.next();
+            }
+            c => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
+
+/// Quote a value if needed, so that [split_unquoted] and [split_unquoted_whitespace] keep it in one part,
+/// and [unquote] returns it unchanged.
+pub fn quote(value: &str) -> Cow<'_, str> {
+    quote_if(value, needs_quotes(value, &SPECIAL_CHARS))
+}
+
+/// Like [quote], but doesn't quote a value only because of colons.
+///
+/// This is for the last part of a log definition, e.g. a file path or a URL, which the parser joins back together at the colons.
+pub fn quote_allowing_colons(value: &str) -> Cow<'_, str> {
+    quote_if(value, needs_quotes(value, &[';', ',', '=', '"']))
+}
+
+fn needs_quotes(value: &str, special_chars: &[char]) -> bool {
+    let escapes_something = |(index, _): (usize, &str)| {
+        value[index + 1..]
+            .chars()
+            .next()
+            .is_none_or(|next| SPECIAL_CHARS.contains(&next))
+    };
+    value.is_empty()
+        || value.contains(special_chars)
+        || value.contains(char::is_whitespace)
+        || value.match_indices('\\').any(escapes_something)
+}
+
+fn quote_if(value: &str, needs_quotes: bool) -> Cow<'_, str> {
+    if needs_quotes {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn split_and_unquote(input: &str, separator: char) -> Vec<String> {
+        split_unquoted(input, separator)
+            .unwrap()
+            .into_iter()
+            .map(|part| unquote(part).into_owned())
+            .collect()
+    }
+
+    #[rstest]
+    #[case("", &[""])]
+    #[case("stderr", &["stderr"])]
+    #[case("INFO:file:/path", &["INFO", "file", "/path"])]
+    #[case("file:", &["file", ""])]
+    #[case("file:\"/path/with:colon\"", &["file", "/path/with:colon"])]
+    #[case("file:/path/with\\:colon", &["file", "/path/with:colon"])]
+    #[case("file:\"C:\\logs\\\"", &["file", "C:\\logs\\"])]
+    #[case("file:C:\\logs\\app.log", &["file", "C", "\\logs\\app.log"])]
+    #[case("file:\"say \"\"hi\"\"\"", &["file", "say \"hi\""])]
+    #[case("file:\\\"quoted\\\"", &["file", "\"quoted\""])]
+    #[case("file:/a\"b:c\"d:e", &["file", "/ab:cd", "e"])]
+    #[case("file:\"\"", &["file", ""])]
+    fn splits_at_colons(#[case] input: &str, #[case] expected: &[&str]) {
+        assert_eq!(expected, split_and_unquote(input, ':'));
+    }
+
+    #[rstest]
+    #[case("gzip > a.gz", false)]
+    #[case("\"gzip > a.gz\"", false)]
+    #[case("\"say \"\"hi\"\"\"", false)]
+    #[case("\"\"", false)]
+    #[case("say \\\"hi\\\"", false)]
+    #[case("gzip > \"a b.gz\"", true)]
+    #[case("\"gzip\" > a.gz", true)]
+    #[case("\"a\"b", true)]
+    fn inner_quotes(#[case] raw: &str, #[case] expected: bool) {
+        assert_eq!(expected, has_inner_quotes(raw));
+    }
+
+    #[rstest]
+    #[case("file:/path", "file:/path", None)]
+    #[case("file:/path;truncate", "file:/path", Some("truncate"))]
+    #[case("file:/path\\;x.log;truncate", "file:/path\\;x.log", Some("truncate"))]
+    #[case("file:\"/a;b\";truncate", "file:\"/a;b\"", Some("truncate"))]
+    #[case("file:/path;", "file:/path", Some(""))]
+    #[case("file:/path;a;b", "file:/path", Some("a;b"))]
+    fn splits_once(#[case] input: &str, #[case] definition: &str, #[case] options: Option<&str>) {
+        assert_eq!(
+            (definition, options),
+            split_once_unquoted(input, ';').unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case("file:\"/path")]
+    #[case("file:/path\"")]
+    #[case("file:\"\"\"")]
+    fn unclosed_quote(#[case] input: &str) {
+        assert_eq!(
+            format!(
+                "Unclosed `\"` in `{input}`. Use `\\\"` for a literal `\"`, or `\"\"` inside of quotes"
+            ),
+            split_unquoted(input, ':').unwrap_err().to_string()
+        );
+    }
+
+    #[rstest]
+    #[case("plain", "plain")]
+    #[case("/path/to/file", "/path/to/file")]
+    #[case("C:\\logs\\app.log", "\"C:\\logs\\app.log\"")]
+    #[case("/path/with:colon", "\"/path/with:colon\"")]
+    #[case("say \"hi\"", "\"say \"\"hi\"\"\"")]
+    #[case("", "\"\"")]
+    #[case(" padded ", "\" padded \"")]
+    #[case("C:\\logs\\", "\"C:\\logs\\\"")]
+    #[case("\\server\\share", "\\server\\share")]
+    #[case("a b", "\"a b\"")]
+    #[case("a\\:b", "\"a\\:b\"")]
+    fn quotes(#[case] value: &str, #[case] expected: &str) {
+        assert_eq!(expected, quote(value));
+    }
+
+    #[rstest]
+    fn round_trip(
+        #[values(
+            "plain",
+            "",
+            " ",
+            "/path/with:colon",
+            "/a;b,c=d",
+            "C:\\logs\\",
+            "C:\\logs\\app.log",
+            "\\\\server\\share\\log.txt",
+            "say \"hi\"",
+            "\"",
+            "\\\"",
+            "\\:",
+            "ümlaut:ß"
+        )]
+        value: &str,
+    ) {
+        let quoted = quote(value);
+        assert_eq!(value, unquote(&quoted));
+        for separator in SPECIAL_CHARS.iter().filter(|c| **c != '"') {
+            let input = format!("{quoted}{separator}{quoted}");
+            assert_eq!(
+                vec![value.to_string(), value.to_string()],
+                split_and_unquote(&input, *separator),
+                "input: {input}"
+            );
+        }
+        let input = format!("{quoted} \t{quoted}");
+        let parts: Vec<_> = split_unquoted_whitespace(&input)
+            .unwrap()
+            .into_iter()
+            .map(|part| unquote(part).into_owned())
+            .collect();
+        assert_eq!(vec![value.to_string(), value.to_string()], parts);
+
+        // Colons are joined back together by the parser
+        let quoted = quote_allowing_colons(value);
+        let input = format!("{quoted};{quoted}");
+        let parts: Vec<_> = split_unquoted(&input, ';')
+            .unwrap()
+            .into_iter()
+            .map(|part| split_and_unquote(part, ':').join(":"))
+            .collect();
+        assert_eq!(vec![value.to_string(), value.to_string()], parts);
+    }
+
+    #[rstest]
+    #[case("", &[])]
+    #[case("  ", &[])]
+    #[case("stderr", &["stderr"])]
+    #[case(" INFO:stderr\tDEBUG:file:\"/a b\"  ", &["INFO:stderr", "DEBUG:file:\"/a b\""])]
+    fn splits_at_whitespace(#[case] input: &str, #[case] expected: &[&str]) {
+        assert_eq!(expected, split_unquoted_whitespace(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("plain", "plain")]
+    #[case("C:\\logs\\app.log", "C:\\logs\\app.log")]
+    #[case("http://localhost:4318", "http://localhost:4318")]
+    #[case("/a;b", "\"/a;b\"")]
+    #[case("C:\\", "\"C:\\\"")]
+    fn quotes_allowing_colons(#[case] value: &str, #[case] expected: &str) {
+        assert_eq!(expected, quote_allowing_colons(value));
+    }
+}
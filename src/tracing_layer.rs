@@ -0,0 +1,446 @@
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_log::NormalizeEvent as _;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+use super::config::LoggingConfig;
+use super::fern::LoggingGuard;
+
+/// Build a [tracing_subscriber] layer that logs `tracing` events to the log destinations
+/// defined by the given configuration, using the same formatters as [init_logging!](crate::init_logging).
+///
+/// Fields of the spans an event is in are rendered in front of the message, e.g.
+/// `[2024-01-01T00:00:00Z INFO my_app] request{id=5}:handle: Some message key=value`.
+///
+/// # Arguments
+/// * `config` - The logging configuration to use.
+/// * `default_level` - The default log level to use if a destination was specified without a log level filter.
+///
+/// # Panics
+/// If a log file with the `lock=fail` option is locked by another process, like [init_logging!](crate::init_logging).
+///
+/// # Example
+/// ```rust
+#[doc = include_str!("../examples/tracing.rs")]
+/// ```
+#[macro_export]
+macro_rules! tracing_layer {
+    ($config:expr, $default_level:expr $(,)?) => {{
+        $crate::_tracing_layer(
+            $config,
+            $default_level,
+            option_env!("CARGO_BIN_NAME"),
+            env!("CARGO_CRATE_NAME"),
+        )
+        .expect("Failed to initialize logging")
+    }};
+}
+
+/// Don't use this function directly, use the [tracing_layer!] macro instead.
+pub fn _tracing_layer(
+    config: LoggingConfig,
+    default_level: log::LevelFilter,
+    cargo_bin_name: Option<&str>,
+    cargo_crate_name: &str,
+) -> Result<LogflagLayer> {
+    crate::child::set_active(&config, default_level);
+    let logger =
+        crate::fern::build_main_logger(config, default_level, cargo_bin_name, cargo_crate_name)?
+            .map(|main_logger| {
+                let (max_level, logger) = main_logger.into_log();
+                (max_level, Arc::from(logger))
+            });
+    Ok(match logger {
+        Some((max_level, logger)) => LogflagLayer {
+            max_level,
+            logger: Some(logger),
+        },
+        // Logging is disabled
+        None => LogflagLayer {
+            max_level: log::LevelFilter::Off,
+            logger: None,
+        },
+    })
+}
+
+/// A [tracing_subscriber::Layer] that forwards `tracing` events to the log destinations of a [LoggingConfig].
+///
+/// Create it with the [tracing_layer!] macro.
+pub struct LogflagLayer {
+    max_level: log::LevelFilter,
+    logger: Option<Arc<dyn log::Log>>,
+}
+
+impl LogflagLayer {
+    /// Get a [LoggingGuard] that flushes the log destinations of this layer when it's dropped.
+    /// Hold on to it until the end of `main`, so that records that network destinations haven't sent yet aren't lost when the process exits.
+    pub fn guard(&self) -> LoggingGuard {
+        LoggingGuard::new(self.logger.clone())
+    }
+}
+
+impl std::fmt::Debug for LogflagLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogflagLayer")
+            .field("max_level", &self.max_level)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The rendered fields of a span, stored in the span's extensions.
+struct SpanFields(String);
+
+impl<S> tracing_subscriber::Layer<S> for LogflagLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    // There's intentionally no `enabled` or `max_level_hint` implementation. They apply to all layers
+    // of the subscriber, so other layers wouldn't see events below our level. Events are filtered in `on_event` instead.
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        // Another LogflagLayer stacked on the same subscriber may already have stored the fields,
+        // and inserting an extension twice panics
+        let mut extensions = span.extensions_mut();
+        match extensions.get_mut::<SpanFields>() {
+            Some(SpanFields(fields)) => *fields = visitor.fields,
+            None => extensions.insert(SpanFields(visitor.fields)),
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        let mut extensions = span.extensions_mut();
+        match extensions.get_mut::<SpanFields>() {
+            Some(SpanFields(fields)) => append_fields(fields, &visitor.fields),
+            None => extensions.insert(SpanFields(visitor.fields)),
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(logger) = &self.logger else {
+            return;
+        };
+        // Events coming from the `log` crate (via `tracing_log::LogTracer`) carry their real metadata in fields
+        let normalized_metadata = event.normalized_metadata();
+        let metadata = normalized_metadata.as_ref().unwrap_or(event.metadata());
+        let enabled = to_log_level(metadata.level()) <= self.max_level
+            && logger.enabled(
+                &log::Metadata::builder()
+                    .level(to_log_level(metadata.level()))
+                    .target(metadata.target())
+                    .build(),
+            );
+        if !enabled {
+            return;
+        }
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let mut message = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                message.push_str(span.name());
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>()
+                    && !fields.is_empty()
+                {
+                    write!(message, "{{{fields}}}").unwrap();
+                }
+                message.push(':');
+            }
+            if !message.is_empty() {
+                message.push(' ');
+            }
+        }
+        message.push_str(&visitor.message);
+        if !visitor.fields.is_empty() {
+            if !visitor.message.is_empty() {
+                message.push(' ');
+            }
+            message.push_str(&visitor.fields);
+        }
+
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("{message}"))
+                .level(to_log_level(metadata.level()))
+                .target(metadata.target())
+                .module_path(metadata.module_path())
+                .file(metadata.file())
+                .line(metadata.line())
+                .build(),
+        );
+    }
+}
+
+/// Renders the `message` field and all other fields as `key=value` pairs.
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => write!(self.message, "{value:?}").unwrap(),
+            // Fields added by `tracing_log` for events coming from the `log` crate
+            name if name.starts_with("log.") => {}
+            name => append_fields(&mut self.fields, &format!("{name}={value:?}")),
+        }
+    }
+}
+
+fn append_fields(fields: &mut String, new_fields: &str) {
+    if new_fields.is_empty() {
+        return;
+    }
+    if !fields.is_empty() {
+        fields.push(' ');
+    }
+    fields.push_str(new_fields);
+}
+
+fn to_log_level(level: &tracing::Level) -> log::Level {
+    match *level {
+        tracing::Level::ERROR => log::Level::Error,
+        tracing::Level::WARN => log::Level::Warn,
+        tracing::Level::INFO => log::Level::Info,
+        tracing::Level::DEBUG => log::Level::Debug,
+        tracing::Level::TRACE => log::Level::Trace,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LogDestination, LogDestinationConfig};
+    use log::LevelFilter;
+    use predicates::Predicate;
+    use tracing_subscriber::layer::SubscriberExt as _;
+
+    fn log_to_file(level: LevelFilter, f: impl FnOnce()) -> String {
+        log_to_file_with_layer(level, CountingLayer::default(), f)
+    }
+
+    /// Like [log_to_file], but with another layer stacked on top of the [LogflagLayer].
+    fn log_to_file_with_layer(
+        level: LevelFilter,
+        other_layer: CountingLayer,
+        f: impl FnOnce(),
+    ) -> String {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let file = tempdir.path().join("logfile");
+        let config = LoggingConfig::new(vec![LogDestinationConfig {
+            destination: LogDestination::file(file.clone()),
+            level: Some(level),
+            most_severe: None,
+            filters: Vec::new(),
+        }]);
+        let layer = _tracing_layer(config, LevelFilter::Info, None, "process_name").unwrap();
+        tracing::subscriber::with_default(
+            tracing_subscriber::registry().with(layer).with(other_layer),
+            f,
+        );
+        std::fs::read_to_string(&file).unwrap()
+    }
+
+    /// A layer that counts the events it sees.
+    #[derive(Clone, Default)]
+    struct CountingLayer(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    impl<S: Subscriber> tracing_subscriber::Layer<S> for CountingLayer {
+        fn on_event(&self, _event: &Event<'_>, _ctx: Context<'_, S>) {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    fn assert_matches(expected_log_regex: &str, actually_logged: &str) {
+        assert!(
+            predicates::str::is_match(expected_log_regex)
+                .unwrap()
+                .eval(actually_logged),
+            "actually_logged: \"{actually_logged}\""
+        );
+    }
+
+    const TIMESTAMP_REGEX: &str = r"(\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)";
+
+    #[test]
+    fn event_without_span() {
+        let logged = log_to_file(LevelFilter::Info, || {
+            tracing::info!(target: "my-test", "test log message");
+        });
+        assert_matches(
+            &format!(r"^\[{TIMESTAMP_REGEX} INFO my-test\] test log message\n$"),
+            &logged,
+        );
+    }
+
+    #[test]
+    fn event_with_fields() {
+        let logged = log_to_file(LevelFilter::Info, || {
+            tracing::warn!(target: "my-test", answer = 42, name = "value", "test log message");
+        });
+        assert_matches(
+            &format!(
+                r#"^\[{TIMESTAMP_REGEX} WARN my-test\] test log message answer=42 name="value"\n$"#
+            ),
+            &logged,
+        );
+    }
+
+    #[test]
+    fn event_in_nested_spans() {
+        let logged = log_to_file(LevelFilter::Info, || {
+            let outer = tracing::info_span!("outer", request = 5);
+            let _outer = outer.enter();
+            let inner = tracing::info_span!("inner");
+            let _inner = inner.enter();
+            tracing::error!(target: "my-test", "test log message");
+        });
+        assert_matches(
+            &format!(
+                r"^\[{TIMESTAMP_REGEX} ERROR my-test\] outer\{{request=5\}}:inner: test log message\n$"
+            ),
+            &logged,
+        );
+    }
+
+    #[test]
+    fn stacked_layers_in_span() {
+        let tempdir = assert_fs::TempDir::new().unwrap();
+        let files = [
+            tempdir.path().join("logfile1"),
+            tempdir.path().join("logfile2"),
+        ];
+        let [layer1, layer2] = files.clone().map(|file| {
+            let config = LoggingConfig::new(vec![LogDestinationConfig {
+                destination: LogDestination::file(file),
+                level: Some(LevelFilter::Info),
+                most_severe: None,
+                filters: Vec::new(),
+            }]);
+            _tracing_layer(config, LevelFilter::Info, None, "process_name").unwrap()
+        });
+        tracing::subscriber::with_default(
+            tracing_subscriber::registry().with(layer1).with(layer2),
+            || {
+                let span = tracing::info_span!("span", a = 1);
+                let _span = span.enter();
+                tracing::info!(target: "my-test", "test log message");
+            },
+        );
+        for file in files {
+            assert_matches(
+                &format!(r"^\[{TIMESTAMP_REGEX} INFO my-test\] span\{{a=1\}}: test log message\n$"),
+                &std::fs::read_to_string(file).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn span_with_recorded_fields() {
+        let logged = log_to_file(LevelFilter::Info, || {
+            let span = tracing::info_span!("span", a = 1, b = tracing::field::Empty);
+            span.record("b", 2);
+            let _span = span.enter();
+            tracing::info!(target: "my-test", "test log message");
+        });
+        assert_matches(
+            &format!(r"^\[{TIMESTAMP_REGEX} INFO my-test\] span\{{a=1 b=2\}}: test log message\n$"),
+            &logged,
+        );
+    }
+
+    #[test]
+    fn level_filter() {
+        let logged = log_to_file(LevelFilter::Warn, || {
+            tracing::info!(target: "my-test", "info message");
+            tracing::warn!(target: "my-test", "warn message");
+        });
+        assert_matches(
+            &format!(r"^\[{TIMESTAMP_REGEX} WARN my-test\] warn message\n$"),
+            &logged,
+        );
+    }
+
+    #[test]
+    fn doesnt_disable_events_for_other_layers() {
+        let other_layer = CountingLayer::default();
+        let logged = log_to_file_with_layer(LevelFilter::Warn, other_layer.clone(), || {
+            tracing::info!(target: "my-test", "info message");
+            tracing::debug!(target: "my-test", "debug message");
+            tracing::warn!(target: "my-test", "warn message");
+        });
+        assert_matches(
+            &format!(r"^\[{TIMESTAMP_REGEX} WARN my-test\] warn message\n$"),
+            &logged,
+        );
+        assert_eq!(3, other_layer.0.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[test]
+    fn event_from_log_crate_uses_its_target() {
+        let logged = log_to_file(LevelFilter::Info, || {
+            tracing_log::format_trace(
+                &log::Record::builder()
+                    .level(log::Level::Info)
+                    .target("log-target")
+                    .args(format_args!("test log message"))
+                    .build(),
+            )
+            .unwrap();
+        });
+        assert_matches(
+            &format!(r"^\[{TIMESTAMP_REGEX} INFO log-target\] test log message\n$"),
+            &logged,
+        );
+    }
+
+    #[test]
+    fn disabled() {
+        let layer = _tracing_layer(
+            LoggingConfig::disabled(),
+            LevelFilter::Info,
+            None,
+            "process_name",
+        )
+        .unwrap();
+        assert!(layer.logger.is_none());
+
+        // Other layers still see all events
+        let other_layer = CountingLayer::default();
+        tracing::subscriber::with_default(
+            tracing_subscriber::registry()
+                .with(layer)
+                .with(other_layer.clone()),
+            || tracing::error!(target: "my-test", "test log message"),
+        );
+        assert_eq!(1, other_layer.0.load(std::sync::atomic::Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::mpsc::{SyncSender, TrySendError};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Lock a mutex, ignoring poisoning. A panic while holding one of our locks doesn't leave the data in an inconsistent state,
+/// and logging should keep working after a panic.
+pub fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Ignore the error of writing a log record or flushing a log destination.
+///
+/// There's nowhere to report it to, since logging it would end up in the log destination that just failed.
+/// Records that can't be written are lost. Destinations that can recover, e.g. by reconnecting, do so themselves.
+pub fn ignore_log_error<T, E>(result: Result<T, E>) {
+    let _ = result;
+}
+
+/// Collect the key-values of a log record with one of our visitors.
+///
+/// Visiting only fails if the visitor returns an error, and our visitors never do, so there's no error to handle.
+pub fn collect_key_values<V>(record: &log::Record) -> V
+where
+    V: Default + for<'kvs> log::kv::VisitSource<'kvs>,
+{
+    let mut visitor = V::default();
+    let _ = record.key_values().visit(&mut visitor);
+    visitor
+}
+
+/// Send a message to a bounded channel, waiting for room until the deadline if it's full.
+///
+/// Used to flush background threads, which must not block forever, e.g. if the thread hangs on a network peer.
+pub fn send_until<T>(
+    sender: &SyncSender<T>,
+    mut message: T,
+    deadline: Instant,
+) -> Result<(), TrySendError<T>> {
+    loop {
+        match sender.try_send(message) {
+            Err(TrySendError::Full(returned)) if Instant::now() < deadline => {
+                message = returned;
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            result => return result,
+        }
+    }
+}
@@ -90,11 +90,19 @@ fn expected_log_regex(filter_level: LevelFilter) -> String {
 }
 
 fn run_cli(default_level: LevelFilter, log_args: &[&str]) -> String {
+    let output = run_cli_raw(default_level, log_args);
+    assert!(output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+fn run_cli_raw(default_level: LevelFilter, log_args: &[&str]) -> std::process::Output {
     let mut args = log_args.to_vec();
     let default_level_str = default_level.to_string();
     args.extend(["--default-level", &default_level_str]);
-    let output = CargoBuild::new()
+    CargoBuild::new()
         .example("integration_test")
+        // Always build with the same features, so tests running in parallel don't rebuild the binary under each other
+        .features("otlp")
         .current_release()
         .current_target()
         .run()
@@ -102,9 +110,7 @@ fn run_cli(default_level: LevelFilter, log_args: &[&str]) -> String {
         .command()
         .args(args)
         .output()
-        .unwrap();
-    assert!(output.status.success());
-    String::from_utf8(output.stderr).unwrap()
+        .unwrap()
 }
 
 fn log_arg_stderr(level: &str) -> String {
@@ -294,4 +300,197 @@ fn levels_are_case_insensitive(
     );
 }
 
+#[rstest]
+fn off_disables_destination_but_not_others() {
+    let logfile = TempLogFile::setup();
+    let stderr = run_cli(
+        LevelFilter::Info,
+        &[
+            "--log",
+            "OFF:stderr",
+            "--log",
+            &log_arg_file("", &logfile.logfile_path()),
+        ],
+    );
+    assert_eq!("", stderr);
+    logfile.assert_was_created_with_content(&expected_log_regex(LevelFilter::Info));
+}
+
+#[rstest]
+fn off_destination_doesnt_create_file() {
+    let logfile = TempLogFile::setup();
+    let stderr = run_cli(
+        LevelFilter::Info,
+        &["--log", &log_arg_file("OFF:", &logfile.logfile_path())],
+    );
+    // Only turning a destination off keeps the default of the integration test binary, `WARN:stderr`
+    assert!(
+        predicates::str::is_match(expected_log_regex(LevelFilter::Warn))
+            .unwrap()
+            .eval(&stderr)
+    );
+    logfile.logfile().assert(predicate::path::missing());
+}
+
+#[rstest]
+fn off_disables_default_destination() {
+    let stderr = run_cli(LevelFilter::Info, &["--log", "OFF:stderr"]);
+    assert_eq!("", stderr);
+}
+
+#[rstest]
+fn level_ranges() {
+    let logfile = TempLogFile::setup();
+    let stderr = run_cli(
+        LevelFilter::Info,
+        &[
+            "--log",
+            &log_arg_file("DEBUG..INFO:", &logfile.logfile_path()),
+            "--log",
+            "=WARN:stderr",
+        ],
+    );
+    let timestamp_regex = r"(\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)";
+    logfile.assert_was_created_with_content(&format!(
+        r"^\[{timestamp_regex} DEBUG integration_test\] Some debug log\n\[{timestamp_regex} INFO integration_test\] Some info log\n$"
+    ));
+    assert!(
+        predicates::str::is_match(format!(
+            r"^\[{timestamp_regex} WARN integration_test\] Some warn log\n$"
+        ))
+        .unwrap()
+        .eval(&stderr)
+    );
+}
+
+#[rstest]
+fn message_filters() {
+    let logfile = TempLogFile::setup();
+    let stderr = run_cli(
+        LevelFilter::Trace,
+        &[
+            "--log",
+            &format!(
+                "{};exclude=\"/^Some (debug|trace) log$/\"",
+                log_arg_file("", &logfile.logfile_path())
+            ),
+            "--log",
+            "stderr;include=warn,include=error",
+        ],
+    );
+    logfile.assert_was_created_with_content(&expected_log_regex(LevelFilter::Info));
+    assert!(
+        predicates::str::is_match(expected_log_regex(LevelFilter::Warn))
+            .unwrap()
+            .eval(&stderr)
+    );
+}
+
+#[rstest]
+fn panic_is_logged_to_file() {
+    let logfile = TempLogFile::setup();
+    let output = run_cli_raw(
+        LevelFilter::Info,
+        &[
+            "--log",
+            &log_arg_file("", &logfile.logfile_path()),
+            "--panic",
+        ],
+    );
+    assert!(!output.status.success());
+    let timestamp_regex = r"(\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z)";
+    logfile.assert_was_created_with_content(&format!(
+        r"\[{timestamp_regex} ERROR panic\] thread 'main' panicked at examples[/\\]integration_test\.rs:\d+:\d+:\nSome panic\n$"
+    ));
+    // The previous panic hook still runs and prints to stderr
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Some panic"), "stderr: \"{stderr}\"");
+}
+
+#[rstest]
+fn tcp_destination_is_flushed_before_exit() {
+    // Without flushing at exit, the process could exit before the background thread even connected
+    for _ in 0..5 {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let stderr = run_cli(
+            LevelFilter::Info,
+            &[
+                "--log",
+                &format!("tcp://{}", listener.local_addr().unwrap()),
+            ],
+        );
+        assert_eq!("", stderr);
+        // The process already exited, but the connection waits in the listen backlog
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = String::new();
+        std::io::Read::read_to_string(&mut stream, &mut received).unwrap();
+        assert!(
+            predicates::str::is_match(expected_log_regex(LevelFilter::Info))
+                .unwrap()
+                .eval(&received),
+            "received: \"{received}\""
+        );
+    }
+}
+
+#[rstest]
+fn otlp_destination_is_flushed_before_exit() {
+    // A mock collector that answers every request and returns the bodies
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                std::io::BufRead::read_line(&mut reader, &mut header).unwrap();
+                let header = header.trim_end().to_ascii_lowercase();
+                if header.is_empty() {
+                    break;
+                }
+                if let Some(value) = header.strip_prefix("content-length: ") {
+                    content_length = value.parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            std::io::Read::read_exact(&mut reader, &mut body).unwrap();
+            // Hand out the body before answering, so it's there once the process exited
+            if sender.send(body).is_err() {
+                return;
+            }
+            std::io::Write::write_all(
+                &mut stream,
+                b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            )
+            .unwrap();
+        }
+    });
+
+    let stderr = run_cli(
+        LevelFilter::Info,
+        &["--log", &format!("otlp+json:{endpoint}")],
+    );
+    assert_eq!("", stderr);
+    // The process already exited, so everything it exported is already there
+    let messages: Vec<String> = receiver
+        .try_iter()
+        .flat_map(|body| {
+            let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            request["resourceLogs"][0]["scopeLogs"][0]["logRecords"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|record| record["body"]["stringValue"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    assert_eq!(
+        vec!["Some info log", "Some warn log", "Some error log"],
+        messages
+    );
+}
+
 // TODO Tests for logging to syslog